@@ -0,0 +1,41 @@
+//! Times `collect()` in a large environment where few variables match, such as a CI runner.
+//!
+//! Run with `cargo bench --bench collect`.
+
+use std::{path::Path, time::Instant};
+
+use config::Source;
+use config_secret::{EnvironmentSecretFile, MapVarProvider};
+
+const VARS: usize = 2000;
+const ITERATIONS: u32 = 1000;
+
+fn main() {
+    let config = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/assets/config.json");
+    let config = config.to_string_lossy();
+
+    let vars: MapVarProvider = (0..VARS)
+        .map(|i| {
+            (
+                format!("RUNNER_TOOL_{}_PATH", i),
+                format!("/opt/tool/{}", i),
+            )
+        })
+        .chain(vec![("APP_SERVER_FILE".to_string(), config.to_string())])
+        .collect();
+    let source = EnvironmentSecretFile::with_prefix("APP").var_provider(vars);
+
+    let start = Instant::now();
+    let mut keys = 0;
+    for _ in 0..ITERATIONS {
+        keys += source.collect().unwrap().len();
+    }
+    let elapsed = start.elapsed();
+    assert!(keys == ITERATIONS as usize);
+
+    println!(
+        "collect() with {} variables: {:?} per call",
+        VARS + 1,
+        elapsed / ITERATIONS
+    );
+}
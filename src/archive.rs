@@ -0,0 +1,179 @@
+use std::{
+    io::{self, Cursor, Read},
+    path::{Path, PathBuf},
+};
+
+use config::{ConfigError, Map, Source, Value};
+
+use crate::{stats::StatsCell, CollectStats, Options, SecretError};
+
+#[derive(Clone, Debug)]
+pub struct TarSecret {
+    /// Path of the archive to load.
+    ///
+    /// Each file of the archive is inserted under the key formed by its path within the
+    /// archive, without extension and with `/` replaced by the key delimiter. For example the
+    /// member `redis/password.json` is inserted under `redis.password`. Directory members are
+    /// skipped.
+    path: PathBuf,
+
+    /// Options controlling how the members of the archive are read, as if each member was a
+    /// secret file.
+    options: Options,
+
+    /// Decompress the archive with gzip. Defaults to decompressing the archives whose name
+    /// ends with `.gz` or `.tgz`, once `.age` is removed.
+    #[cfg(feature = "gzip")]
+    gzip: Option<bool>,
+
+    /// Character sequence joining the directories and the name of a member into its key.
+    /// Defaults to `.`, which `config` nests into tables.
+    key_delimiter: String,
+
+    /// Statistics of the last successful `collect()`, counting the members of the archive.
+    stats: StatsCell,
+}
+
+impl TarSecret {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            options: Options::default(),
+            #[cfg(feature = "gzip")]
+            gzip: None,
+            key_delimiter: ".".into(),
+            stats: StatsCell::default(),
+        }
+    }
+
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    #[cfg(feature = "gzip")]
+    pub fn gzip(mut self, gzip: bool) -> Self {
+        self.gzip = Some(gzip);
+        self
+    }
+
+    pub fn key_delimiter(mut self, s: &str) -> Self {
+        self.key_delimiter = s.into();
+        self
+    }
+
+    /// Returns the number of members read and their total size during the last successful
+    /// `collect()`.
+    pub fn last_stats(&self) -> CollectStats {
+        self.stats.get()
+    }
+
+    fn open(&self) -> Result<Box<dyn Read>, SecretError> {
+        let bytes = self.options.read_bytes(&self.path)?;
+
+        #[cfg(feature = "gzip")]
+        {
+            let name = self.options.format_path(&self.path);
+            let name = name.to_string_lossy();
+            let gzip = self
+                .gzip
+                .unwrap_or_else(|| name.ends_with(".gz") || name.ends_with(".tgz"));
+            if gzip {
+                // Decompressed beforehand so that a corrupted archive is reported as such
+                let mut decompressed = Vec::new();
+                flate2::read::GzDecoder::new(&bytes[..])
+                    .read_to_end(&mut decompressed)
+                    .map_err(|source| SecretError::Decompress {
+                        path: self.path.clone(),
+                        source,
+                    })?;
+                return Ok(Box::new(Cursor::new(decompressed)));
+            }
+        }
+
+        Ok(Box::new(Cursor::new(bytes)))
+    }
+
+    fn read_error(&self, source: io::Error) -> SecretError {
+        SecretError::Read {
+            path: self.path.clone(),
+            source,
+        }
+    }
+
+    /// Returns the config key of the archive member at `path`.
+    fn member_key(&self, path: &Path) -> String {
+        let mut segments: Vec<String> = path
+            .parent()
+            .into_iter()
+            .flat_map(|parent| parent.components())
+            .filter_map(|component| match component {
+                std::path::Component::Normal(segment) => {
+                    Some(segment.to_string_lossy().into_owned())
+                }
+                _ => None,
+            })
+            .collect();
+
+        if let Some(stem) = path.file_stem() {
+            segments.push(stem.to_string_lossy().into_owned());
+        }
+
+        segments.join(&self.key_delimiter)
+    }
+}
+
+impl Source for TarSecret {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new((*self).clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut m = Map::new();
+        let mut stats = CollectStats::default();
+
+        let mut archive = tar::Archive::new(self.open()?);
+        let entries = archive.entries().map_err(|err| self.read_error(err))?;
+
+        for entry in entries {
+            let mut entry = entry.map_err(|err| self.read_error(err))?;
+
+            // Skip directories, links and other special members
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let path = entry
+                .path()
+                .map_err(|err| self.read_error(err))?
+                .into_owned();
+            let member = path.display().to_string();
+
+            let key = self.member_key(&path);
+            let options = self.options.for_key(&key);
+
+            let mut bytes = Vec::new();
+            let loaded = entry
+                .read_to_end(&mut bytes)
+                .map_err(|err| self.read_error(err))
+                .and_then(|len| {
+                    stats.record(len as u64);
+                    let uri = format!("{}:{}", self.path.display(), member);
+                    options.parse_bytes(&path, &uri, bytes)
+                })
+                .map_err(|source| SecretError::Member {
+                    archive: self.path.clone(),
+                    member: member.clone(),
+                    source: Box::new(source),
+                })?;
+
+            let archive = self.path.display().to_string();
+            let uri = format!("{}:{}", archive, member);
+            let loaded = self.options.label(loaded, &key, &archive, &uri);
+            m.insert(key, loaded);
+        }
+
+        self.stats.set(stats);
+        Ok(m)
+    }
+}
@@ -0,0 +1,23 @@
+/// Environment variables that look related to a source but were not loaded.
+///
+/// Only variable names are reported, never their values.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AuditReport {
+    /// Variables starting with the prefix but not ending with the suffix.
+    pub prefix_without_suffix: Vec<String>,
+
+    /// Variables ending with the suffix but not starting with the prefix.
+    pub suffix_without_prefix: Vec<String>,
+
+    /// Matching variables skipped because their value is empty.
+    pub empty: Vec<String>,
+}
+
+impl AuditReport {
+    /// Whether no suspicious variable was found.
+    pub fn is_empty(&self) -> bool {
+        self.prefix_without_suffix.is_empty()
+            && self.suffix_without_prefix.is_empty()
+            && self.empty.is_empty()
+    }
+}
@@ -0,0 +1,263 @@
+use std::{env, fmt, process::Command};
+
+use config::{ConfigError, Map, Source, Value};
+
+use crate::{
+    cloud::{field, metadata, payload_value, remote_error, send},
+    SecretError,
+};
+
+const API_VERSION: &str = "7.4";
+const RESOURCE: &str = "https://vault.azure.net";
+const SCOPE: &str = "https://vault.azure.net/.default";
+const IMDS_TOKEN_URI: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
+
+/// Secrets read by an `AzureKeyVaultSource`.
+#[derive(Clone, Debug)]
+enum Selection {
+    /// A single secret inserted under a config key.
+    Name { key: String, name: String },
+
+    /// Every enabled secret whose name starts with the prefix.
+    Prefix(String),
+}
+
+/// Source reading secrets from an Azure Key Vault.
+///
+/// Values are inserted as tables when they are JSON objects and as strings otherwise.
+/// Requests are authenticated with the default credential chain: a service principal from
+/// `AZURE_TENANT_ID`, `AZURE_CLIENT_ID` and `AZURE_CLIENT_SECRET`, the managed identity of
+/// the host, then the account of the Azure CLI.
+#[derive(Clone)]
+pub struct AzureKeyVaultSource {
+    /// URL of the vault, such as `https://my-vault.vault.azure.net`.
+    vault_url: String,
+
+    /// Secrets to read.
+    ///
+    /// With a prefix, each secret is inserted under its name without the prefix, lowercased,
+    /// `--` nesting keys since Key Vault names only allow letters, digits and dashes. For
+    /// example with a prefix of `app-`, `app-redis--password` is inserted under
+    /// `redis.password`.
+    selection: Selection,
+
+    /// OAuth access token used instead of the default credential chain.
+    access_token: Option<String>,
+
+    /// Fail when the named secret does not exist instead of inserting nothing.
+    ///
+    /// Defaults to `true`. Enumerating a prefix matching no secret is never an error.
+    required: bool,
+}
+
+impl AzureKeyVaultSource {
+    pub fn new(vault_url: &str, key: &str, name: &str) -> Self {
+        Self::with_selection(
+            vault_url,
+            Selection::Name {
+                key: key.into(),
+                name: name.into(),
+            },
+        )
+    }
+
+    pub fn with_prefix(vault_url: &str, prefix: &str) -> Self {
+        Self::with_selection(vault_url, Selection::Prefix(prefix.into()))
+    }
+
+    fn with_selection(vault_url: &str, selection: Selection) -> Self {
+        Self {
+            vault_url: vault_url.trim_end_matches('/').into(),
+            selection,
+            access_token: None,
+            required: true,
+        }
+    }
+
+    pub fn access_token(mut self, token: &str) -> Self {
+        self.access_token = Some(token.into());
+        self
+    }
+
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Sends an authenticated request to `url` of the vault, `None` meaning a 404.
+    fn get(
+        &self,
+        resource: &str,
+        token: &str,
+        url: &str,
+    ) -> Result<Option<serde_json::Value>, SecretError> {
+        let request = ureq::get(url).set("Authorization", &format!("Bearer {}", token));
+        send(resource, request, None)
+    }
+
+    /// Reads the value of the secret `name`, `None` meaning that it does not exist.
+    fn value(&self, token: &str, name: &str) -> Result<Option<String>, SecretError> {
+        let resource = format!("{}/secrets/{}", self.vault_url, name);
+        let url = format!("{}?api-version={}", resource, API_VERSION);
+
+        match self.get(&resource, token, &url)? {
+            Some(response) => field(&response, "value")
+                .map(Some)
+                .ok_or_else(|| remote_error(&resource, "response without value")),
+            None => Ok(None),
+        }
+    }
+
+    /// Lists the names of the enabled secrets of the vault starting with `prefix`.
+    fn names(&self, token: &str, prefix: &str) -> Result<Vec<String>, SecretError> {
+        let resource = format!("{}/secrets", self.vault_url);
+        let mut next = Some(format!("{}?api-version={}", resource, API_VERSION));
+        let mut names = Vec::new();
+
+        while let Some(url) = next.take() {
+            let page = match self.get(&resource, token, &url)? {
+                Some(page) => page,
+                None => break,
+            };
+
+            for item in page["value"].as_array().into_iter().flatten() {
+                let enabled = item["attributes"]["enabled"].as_bool().unwrap_or(true);
+                let name = field(item, "id").and_then(|id| id.rsplit('/').next().map(String::from));
+                if let Some(name) = name.filter(|name| enabled && name.starts_with(prefix)) {
+                    names.push(name);
+                }
+            }
+
+            next = field(&page, "nextLink");
+        }
+
+        names.sort();
+        Ok(names)
+    }
+
+    /// Returns the access token of the requests.
+    fn token(&self) -> Result<String, SecretError> {
+        match self.access_token {
+            Some(ref token) => Ok(token.clone()),
+            None => default_token(&self.vault_url),
+        }
+    }
+}
+
+/// Returns the config key of the secret `name` enumerated with `prefix`.
+fn prefixed_key(prefix: &str, name: &str) -> String {
+    name[prefix.len()..].to_lowercase().replace("--", ".")
+}
+
+/// Returns an access token for Key Vault from the default credential chain.
+fn default_token(resource: &str) -> Result<String, SecretError> {
+    let var = |name| env::var(name).ok().filter(|value| !value.is_empty());
+
+    if let (Some(tenant), Some(client_id), Some(client_secret)) = (
+        var("AZURE_TENANT_ID"),
+        var("AZURE_CLIENT_ID"),
+        var("AZURE_CLIENT_SECRET"),
+    ) {
+        let url = format!(
+            "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
+            tenant
+        );
+        let form = [
+            ("grant_type", "client_credentials"),
+            ("client_id", client_id.as_str()),
+            ("client_secret", client_secret.as_str()),
+            ("scope", SCOPE),
+        ];
+
+        return send(resource, ureq::post(&url), Some(&form))?
+            .and_then(|response| field(&response, "access_token"))
+            .ok_or_else(|| remote_error(resource, "token endpoint returned no access token"));
+    }
+
+    let managed = match (var("IDENTITY_ENDPOINT"), var("IDENTITY_HEADER")) {
+        (Some(endpoint), Some(header)) => metadata(
+            ureq::get(&endpoint)
+                .query("resource", RESOURCE)
+                .query("api-version", "2019-08-01")
+                .set("X-IDENTITY-HEADER", &header),
+        ),
+        _ => metadata(
+            ureq::get(IMDS_TOKEN_URI)
+                .query("resource", RESOURCE)
+                .query("api-version", "2018-02-01")
+                .set("Metadata", "true"),
+        ),
+    };
+    if let Some(token) = managed.and_then(|response| field(&response, "access_token")) {
+        return Ok(token);
+    }
+
+    cli_token().ok_or_else(|| remote_error(resource, "no Azure credentials found"))
+}
+
+/// Returns an access token of the account logged in the Azure CLI.
+fn cli_token() -> Option<String> {
+    let program = if cfg!(windows) { "az.cmd" } else { "az" };
+    let output = Command::new(program)
+        .args(["account", "get-access-token", "--output", "json"])
+        .args(["--resource", RESOURCE])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let response = serde_json::from_slice(&output.stdout).ok()?;
+    field(&response, "accessToken")
+}
+
+// The access token is a credential, only whether it is set is shown
+impl fmt::Debug for AzureKeyVaultSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AzureKeyVaultSource")
+            .field("vault_url", &self.vault_url)
+            .field("selection", &self.selection)
+            .field(
+                "access_token",
+                &self.access_token.as_ref().map(|_| "<redacted>"),
+            )
+            .field("required", &self.required)
+            .finish()
+    }
+}
+
+impl Source for AzureKeyVaultSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new((*self).clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut m = Map::new();
+        let token = self.token()?;
+
+        match self.selection {
+            Selection::Name { ref key, ref name } => match self.value(&token, name)? {
+                Some(value) => {
+                    let uri = format!("{}/secrets/{}", self.vault_url, name);
+                    m.insert(key.clone(), payload_value(&uri, value));
+                }
+                None if self.required => {
+                    let resource = format!("{}/secrets/{}", self.vault_url, name);
+                    return Err(remote_error(&resource, "secret not found").into());
+                }
+                None => {}
+            },
+            Selection::Prefix(ref prefix) => {
+                for name in self.names(&token, prefix)? {
+                    // A secret deleted since the listing is skipped
+                    if let Some(value) = self.value(&token, &name)? {
+                        let uri = format!("{}/secrets/{}", self.vault_url, name);
+                        m.insert(prefixed_key(prefix, &name), payload_value(&uri, value));
+                    }
+                }
+            }
+        }
+
+        Ok(m)
+    }
+}
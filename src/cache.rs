@@ -0,0 +1,44 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use config::{ConfigError, Map, Value};
+
+/// Result of a previous collect, shared between the clones of a source.
+#[derive(Clone, Default)]
+pub(crate) struct Cache(Arc<Mutex<Option<Map<String, Value>>>>);
+
+impl Cache {
+    /// Returns the cached map, or collects and caches it when empty.
+    ///
+    /// Failed collects are not cached.
+    pub(crate) fn get_or_try_insert(
+        &self,
+        collect: impl FnOnce() -> Result<Map<String, Value>, ConfigError>,
+    ) -> Result<Map<String, Value>, ConfigError> {
+        let mut cached = self.0.lock().unwrap_or_else(|err| err.into_inner());
+
+        if let Some(ref map) = *cached {
+            return Ok(map.clone());
+        }
+
+        let map = collect()?;
+        *cached = Some(map.clone());
+        Ok(map)
+    }
+
+    pub(crate) fn invalidate(&self) {
+        *self.0.lock().unwrap_or_else(|err| err.into_inner()) = None;
+    }
+}
+
+// The cached map holds the secrets themselves, only whether it is filled is shown
+impl fmt::Debug for Cache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cached = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        f.debug_struct("Cache")
+            .field("cached", &cached.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,96 @@
+use config::{Map, Value, ValueKind};
+
+/// Case applied to config keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyCase {
+    /// Keep keys exactly as they were produced.
+    AsIs,
+
+    /// Convert keys to lowercase.
+    Lower,
+
+    /// Convert keys to uppercase.
+    Upper,
+
+    /// Convert keys to `snake_case`.
+    #[cfg(feature = "case")]
+    Snake,
+
+    /// Convert keys to `kebab-case`.
+    #[cfg(feature = "case")]
+    Kebab,
+
+    /// Convert keys to `camelCase`.
+    #[cfg(feature = "case")]
+    Camel,
+
+    /// Convert keys to `PascalCase`.
+    #[cfg(feature = "case")]
+    Pascal,
+}
+
+#[allow(clippy::derivable_impls)] // `#[default]` on variants requires Rust 1.62
+impl Default for KeyCase {
+    fn default() -> Self {
+        KeyCase::AsIs
+    }
+}
+
+impl KeyCase {
+    /// Converts a single key to this case.
+    pub fn apply(&self, key: &str) -> String {
+        match self {
+            KeyCase::AsIs => key.to_string(),
+            KeyCase::Lower => key.to_lowercase(),
+            KeyCase::Upper => key.to_uppercase(),
+            #[cfg(feature = "case")]
+            KeyCase::Snake => heck::ToSnakeCase::to_snake_case(key),
+            #[cfg(feature = "case")]
+            KeyCase::Kebab => heck::ToKebabCase::to_kebab_case(key),
+            #[cfg(feature = "case")]
+            KeyCase::Camel => heck::ToLowerCamelCase::to_lower_camel_case(key),
+            #[cfg(feature = "case")]
+            KeyCase::Pascal => heck::ToUpperCamelCase::to_upper_camel_case(key),
+        }
+    }
+
+    /// Converts each segment of a key nested with `delimiter`, keeping the nesting.
+    pub(crate) fn apply_nested(&self, key: &str, delimiter: &str) -> String {
+        if *self == KeyCase::AsIs || delimiter.is_empty() {
+            return self.apply(key);
+        }
+
+        key.split(delimiter)
+            .map(|segment| self.apply(segment))
+            .collect::<Vec<_>>()
+            .join(delimiter)
+    }
+
+    /// Converts every key of `value`, including keys of nested tables.
+    ///
+    /// Converted values are rebuilt with `origin` since they all come from the same file.
+    pub(crate) fn apply_value(&self, value: Value, origin: Option<&String>) -> Value {
+        if *self == KeyCase::AsIs {
+            return value;
+        }
+
+        let kind = match value.kind {
+            ValueKind::Table(map) => ValueKind::Table(self.apply_map(map, origin)),
+            ValueKind::Array(values) => ValueKind::Array(
+                values
+                    .into_iter()
+                    .map(|v| self.apply_value(v, origin))
+                    .collect(),
+            ),
+            kind => kind,
+        };
+
+        Value::new(origin, kind)
+    }
+
+    fn apply_map(&self, map: Map<String, Value>, origin: Option<&String>) -> Map<String, Value> {
+        map.into_iter()
+            .map(|(key, value)| (self.apply(&key), self.apply_value(value, origin)))
+            .collect()
+    }
+}
@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use config::{Map, Value, ValueKind};
+use serde_json::Value as Json;
+
+use crate::SecretError;
+
+/// Timeout of the requests to secret managers and token endpoints.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Timeout of the requests to metadata servers, which are unreachable outside of the cloud.
+const METADATA_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Builds the error of a failed request for the secret `resource`.
+pub(crate) fn remote_error(resource: &str, reason: impl ToString) -> SecretError {
+    SecretError::Remote {
+        resource: resource.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Sends `request` and returns the JSON body of the response, `None` meaning a 404.
+pub(crate) fn send(
+    resource: &str,
+    request: ureq::Request,
+    form: Option<&[(&str, &str)]>,
+) -> Result<Option<Json>, SecretError> {
+    let response = match form {
+        Some(form) => request.timeout(TIMEOUT).send_form(form),
+        None => request.timeout(TIMEOUT).call(),
+    };
+
+    match response {
+        Ok(response) => {
+            let body = response
+                .into_string()
+                .map_err(|err| remote_error(resource, err))?;
+            serde_json::from_str(&body)
+                .map(Some)
+                .map_err(|err| remote_error(resource, err))
+        }
+        Err(ureq::Error::Status(404, _)) => Ok(None),
+        Err(ureq::Error::Status(status, response)) => {
+            // Error bodies of the APIs describe the failure, never the secret
+            let body = response.into_string().unwrap_or_default();
+            Err(remote_error(
+                resource,
+                format!("status {}: {}", status, body),
+            ))
+        }
+        Err(err) => Err(remote_error(resource, err)),
+    }
+}
+
+/// Sends a request to a metadata server, `None` meaning that it is not reachable.
+pub(crate) fn metadata(request: ureq::Request) -> Option<Json> {
+    let body = request
+        .timeout(METADATA_TIMEOUT)
+        .call()
+        .ok()?
+        .into_string()
+        .ok()?;
+    serde_json::from_str(&body).ok()
+}
+
+/// Returns the string field `field` of `json`.
+pub(crate) fn field(json: &Json, field: &str) -> Option<String> {
+    json.get(field)?.as_str().map(String::from)
+}
+
+/// Converts the payload of a secret into a config value.
+///
+/// A JSON object becomes a table, any other payload a string.
+pub(crate) fn payload_value(uri: &str, payload: String) -> Value {
+    let uri = Some(uri.to_string());
+    match serde_json::from_str::<Json>(&payload) {
+        Ok(json @ Json::Object(_)) => from_json(uri.as_ref(), json),
+        _ => Value::new(uri.as_ref(), ValueKind::String(payload)),
+    }
+}
+
+fn from_json(uri: Option<&String>, json: Json) -> Value {
+    let kind = match json {
+        Json::Null => ValueKind::Nil,
+        Json::Bool(value) => ValueKind::Boolean(value),
+        Json::Number(number) => match number.as_i64() {
+            Some(value) => ValueKind::I64(value),
+            None => match number.as_u64() {
+                Some(value) => ValueKind::U64(value),
+                None => ValueKind::Float(number.as_f64().unwrap_or_default()),
+            },
+        },
+        Json::String(value) => ValueKind::String(value),
+        Json::Array(values) => ValueKind::Array(
+            values
+                .into_iter()
+                .map(|value| from_json(uri, value))
+                .collect(),
+        ),
+        Json::Object(map) => ValueKind::Table(
+            map.into_iter()
+                .map(|(key, value)| (key, from_json(uri, value)))
+                .collect::<Map<_, _>>(),
+        ),
+    };
+
+    Value::new(uri, kind)
+}
@@ -0,0 +1,51 @@
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::SecretError;
+
+/// Extension of age encrypted files, removed to find the format of their content.
+const EXTENSION: &str = "age";
+
+/// Whether the file at `path` is age encrypted according to its extension.
+pub(crate) fn is_encrypted(path: &Path) -> bool {
+    matches!(path.extension(), Some(extension) if extension == EXTENSION)
+}
+
+/// Returns the path of the content of the encrypted file at `path`, such as `db.json` for
+/// `db.json.age`.
+pub(crate) fn plain_path(path: &Path) -> PathBuf {
+    path.with_extension("")
+}
+
+/// Decrypts the age encrypted `bytes` of the file at `path` with the identity file whose
+/// content is `identities`.
+///
+/// Errors only describe why decryption failed, never the content of either file.
+pub(crate) fn decrypt(
+    path: &Path,
+    bytes: &[u8],
+    identities: &[u8],
+) -> Result<Vec<u8>, SecretError> {
+    let error = |reason: String| SecretError::Decrypt {
+        path: path.to_path_buf(),
+        reason,
+    };
+
+    let identities = age::IdentityFile::from_buffer(identities)
+        .map_err(|err| error(format!("invalid identity file: {}", err)))?
+        .into_identities()
+        .map_err(|err| error(format!("invalid identity file: {}", err)))?;
+
+    let decryptor = age::Decryptor::new_buffered(bytes).map_err(|err| error(err.to_string()))?;
+    let mut reader = decryptor
+        .decrypt(identities.iter().map(|identity| identity.as_ref()))
+        .map_err(|err| error(err.to_string()))?;
+
+    let mut plain = Vec::new();
+    reader
+        .read_to_end(&mut plain)
+        .map_err(|err| error(err.to_string()))?;
+    Ok(plain)
+}
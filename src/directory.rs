@@ -0,0 +1,407 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use config::{ConfigError, Map, Source, Value};
+
+use crate::{load::load_counted, stats::StatsCell, CollectStats, Options, SecretError};
+
+/// Directory where Docker Swarm mounts the secrets of a service.
+const DOCKER_SECRETS: &str = "/run/secrets";
+
+/// Order in which the files of a directory are loaded.
+///
+/// When several files map to the same key, the last one loaded wins.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOrder {
+    /// By file name, in ascending order.
+    Name,
+
+    /// By file name, in descending order.
+    NameDesc,
+
+    /// In the order given by the operating system, which may change across runs.
+    None,
+}
+
+#[allow(clippy::derivable_impls)] // `#[default]` on variants requires Rust 1.62
+impl Default for SortOrder {
+    fn default() -> Self {
+        SortOrder::Name
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DirectorySecret {
+    /// Paths of the directories to load, such as `/run/secrets`.
+    ///
+    /// Each file of a directory is inserted under its name without extension, so that
+    /// `redis.json` is inserted under `redis`, unless `full_names`. Subdirectories are skipped
+    /// unless `recursive`. Directories are loaded in order, a file of a later directory
+    /// replacing the file of an earlier one inserted under the same key.
+    paths: Vec<PathBuf>,
+
+    /// Options controlling how the files of the directory are read.
+    options: Options,
+
+    /// Extensions of the files to load, compared case-insensitively, other files being
+    /// skipped. Defaults to every file.
+    extensions: Option<HashSet<String>>,
+
+    /// Order in which the files are loaded. Defaults to `SortOrder::Name`.
+    sort: SortOrder,
+
+    /// Load the files of subdirectories, their names becoming key segments.
+    ///
+    /// For example `db/password` is inserted under `db.password`. Links to directories are
+    /// not followed. Defaults to `false`.
+    recursive: bool,
+
+    /// Insert each file under its full name, extension included, such as the `tls.crt` and
+    /// `tls.key` of a Kubernetes TLS secret which would otherwise both be inserted under
+    /// `tls`. Defaults to `false`.
+    full_names: bool,
+
+    /// Character sequence joining the names of the subdirectories and of the file into its
+    /// key when `recursive`. Defaults to `.`, which `config` nests into tables.
+    key_delimiter: String,
+
+    /// Maximum number of subdirectory levels loaded when `recursive`, `0` loading only the
+    /// files of the directory itself. Defaults to no limit.
+    max_depth: Option<usize>,
+
+    /// Maximum number of keys loaded, guarding against a directory mounted at the wrong place,
+    /// such as `/`. Files are counted while the directory is walked, so that loading aborts
+    /// as soon as the limit is exceeded. Defaults to no limit.
+    max_keys: Option<usize>,
+
+    /// Skip the files whose content cannot be parsed, logging a warning with the `tracing`
+    /// feature, instead of failing.
+    ///
+    /// Only malformed content is tolerated: a file that cannot be read, or whose format
+    /// cannot be inferred, still fails. Defaults to `false`.
+    skip_invalid: bool,
+
+    /// Variable naming the subdirectory of `path` to load, such as `APP_ENV` selecting
+    /// `/secrets/prod` or `/secrets/staging`, read from the provider of the options. Defaults
+    /// to none, loading `path` itself.
+    switch: Option<String>,
+
+    /// Subdirectory loaded when the `switch` variable is not set, which fails otherwise.
+    switch_default: Option<String>,
+
+    /// Fail when a directory does not exist instead of loading nothing from it. Defaults to
+    /// `true`.
+    required: bool,
+
+    /// Statistics of the last successful `collect()`.
+    stats: StatsCell,
+}
+
+impl DirectorySecret {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self::from_dirs(vec![path.into()])
+    }
+
+    /// Loads several directories layered by precedence, such as `/secrets/common` then
+    /// `/secrets/prod`, the later ones overriding the keys of the earlier ones.
+    pub fn from_dirs(paths: Vec<PathBuf>) -> Self {
+        Self {
+            paths,
+            options: Options::default(),
+            extensions: None,
+            sort: SortOrder::default(),
+            recursive: false,
+            full_names: false,
+            key_delimiter: ".".into(),
+            max_depth: None,
+            max_keys: None,
+            skip_invalid: false,
+            switch: None,
+            switch_default: None,
+            required: true,
+            stats: StatsCell::default(),
+        }
+    }
+
+    /// Loads the subdirectory of `base` named by the variable `var`, read at each `collect()`.
+    /// With `from_dirs`, the subdirectory of each directory is loaded.
+    ///
+    /// The value must be a plain directory name, such as `prod`, and not a path.
+    pub fn from_env_switch(base: impl Into<PathBuf>, var: &str) -> Self {
+        Self {
+            switch: Some(var.into()),
+            ..Self::new(base)
+        }
+    }
+
+    /// Preset for a Kubernetes secret volume, such as `/etc/secrets/db`, where each key of the
+    /// secret is a file holding its raw value.
+    ///
+    /// Files are read raw, without their trailing newline, and inserted under their full name
+    /// since the keys of a secret often have an extension, such as `tls.crt`. The `..data`
+    /// links maintained by the kubelet are skipped since subdirectories are not loaded.
+    pub fn kubernetes(path: impl Into<PathBuf>) -> Self {
+        Self::new(path)
+            .options(Options::new().raw(true))
+            .full_names(true)
+    }
+
+    /// Preset for Docker Swarm secrets mounted in `path`, each file holding the raw value of
+    /// the secret named after it.
+    ///
+    /// Files are read raw, without their trailing newline, and inserted under their full name.
+    /// A missing directory loads nothing, so that the same binary runs outside of Swarm.
+    pub fn swarm(path: impl Into<PathBuf>) -> Self {
+        Self::new(path)
+            .options(Options::new().raw(true))
+            .full_names(true)
+            .required(false)
+    }
+
+    /// Preset for the Docker Swarm secrets of the current container, mounted in `/run/secrets`.
+    pub fn docker_default() -> Self {
+        Self::swarm(DOCKER_SECRETS)
+    }
+
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn extensions<'a>(mut self, extensions: impl IntoIterator<Item = &'a str>) -> Self {
+        let extensions = extensions.into_iter().map(|e| e.to_lowercase());
+        self.extensions
+            .get_or_insert_with(HashSet::new)
+            .extend(extensions);
+        self
+    }
+
+    pub fn sort(mut self, order: SortOrder) -> Self {
+        self.sort = order;
+        self
+    }
+
+    pub fn recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    pub fn full_names(mut self, full_names: bool) -> Self {
+        self.full_names = full_names;
+        self
+    }
+
+    pub fn key_delimiter(mut self, s: &str) -> Self {
+        self.key_delimiter = s.into();
+        self
+    }
+
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    pub fn max_keys(mut self, max: usize) -> Self {
+        self.max_keys = Some(max);
+        self
+    }
+
+    pub fn skip_invalid(mut self, skip: bool) -> Self {
+        self.skip_invalid = skip;
+        self
+    }
+
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    pub fn switch_default(mut self, subdir: &str) -> Self {
+        self.switch_default = Some(subdir.into());
+        self
+    }
+
+    /// Returns the number of files read and their total size during the last successful
+    /// `collect()`.
+    pub fn last_stats(&self) -> CollectStats {
+        self.stats.get()
+    }
+
+    /// Returns the directories to load, selected by the `switch` variable if any.
+    fn dirs(&self) -> Result<Vec<PathBuf>, SecretError> {
+        let var = match self.switch {
+            Some(ref var) => var,
+            None => return Ok(self.paths.clone()),
+        };
+
+        let subdir = self
+            .options
+            .var(var)
+            .filter(|value| !value.is_empty())
+            .or_else(|| self.switch_default.clone())
+            .ok_or_else(|| SecretError::UnsetSwitch { var: var.clone() })?;
+
+        let mut components = Path::new(&subdir).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None) => {
+                Ok(self.paths.iter().map(|path| path.join(&subdir)).collect())
+            }
+            _ => Err(SecretError::InvalidOptions(format!(
+                "environment variable {} must name a subdirectory, got `{}`",
+                var, subdir
+            ))),
+        }
+    }
+
+    /// Whether the file at `path` passes the extension filter.
+    fn accepts(&self, path: &Path) -> bool {
+        match self.extensions {
+            Some(ref extensions) => path
+                .extension()
+                .map(|e| extensions.contains(&e.to_string_lossy().to_lowercase()))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+
+    /// Lists the files of `dir` to load, relative to it and sorted.
+    fn files(&self, dir: &Path) -> Result<Vec<PathBuf>, SecretError> {
+        let mut files = Vec::new();
+        self.walk(
+            dir,
+            &self.options.resolve_path(dir),
+            Path::new(""),
+            0,
+            &mut files,
+            &mut HashSet::new(),
+        )?;
+
+        match self.sort {
+            SortOrder::Name => files.sort(),
+            SortOrder::NameDesc => files.sort_by(|a, b| b.cmp(a)),
+            SortOrder::None => {}
+        }
+
+        Ok(files)
+    }
+
+    /// Returns the config key of the file at `relative` path within the directory.
+    fn file_key(&self, relative: &Path) -> Option<String> {
+        let mut segments: Vec<String> = relative
+            .parent()
+            .into_iter()
+            .flat_map(|parent| parent.iter())
+            .map(|segment| segment.to_string_lossy().into_owned())
+            .collect();
+
+        let name = if self.full_names {
+            relative.file_name()?
+        } else {
+            relative.file_stem()?
+        };
+        segments.push(name.to_string_lossy().into_owned());
+        Some(segments.join(&self.key_delimiter))
+    }
+
+    /// Adds the files of `dir`, at `depth` below the loaded directory `root`, to `files`.
+    ///
+    /// `keys` holds the keys of the files found so far when `max_keys` is set.
+    fn walk(
+        &self,
+        root: &Path,
+        dir: &Path,
+        relative: &Path,
+        depth: usize,
+        files: &mut Vec<PathBuf>,
+        keys: &mut HashSet<String>,
+    ) -> Result<(), SecretError> {
+        let read_error = |source| SecretError::Read {
+            path: dir.to_path_buf(),
+            source,
+        };
+
+        for entry in fs::read_dir(dir).map_err(read_error)? {
+            let entry = entry.map_err(read_error)?;
+            let path = entry.path();
+
+            // Links to directories are not followed, to avoid loops and the duplicated
+            // `..data` entries of Kubernetes secret volumes
+            if entry.file_type().map_err(read_error)?.is_dir() {
+                if self.recursive && !matches!(self.max_depth, Some(max) if depth >= max) {
+                    let relative = relative.join(entry.file_name());
+                    self.walk(root, &path, &relative, depth + 1, files, keys)?;
+                }
+                continue;
+            }
+
+            if path.is_file() && self.accepts(&path) {
+                let relative = relative.join(entry.file_name());
+                if let (Some(max), Some(key)) = (self.max_keys, self.file_key(&relative)) {
+                    keys.insert(key);
+                    if keys.len() > max {
+                        return Err(SecretError::TooManyKeys {
+                            path: root.to_path_buf(),
+                            max,
+                        });
+                    }
+                }
+                files.push(relative);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Source for DirectorySecret {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new((*self).clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut m = Map::new();
+        let mut stats = CollectStats::default();
+
+        for dir in self.dirs()? {
+            // The directory is listed from the filesystem whatever the reader of the options
+            if !self.required && !self.options.resolve_path(&dir).is_dir() {
+                continue;
+            }
+
+            for relative in self.files(&dir)? {
+                let key = match self.file_key(&relative) {
+                    Some(key) => key,
+                    None => continue,
+                };
+                let path = dir.join(&relative);
+
+                let loaded = match load_counted(&path, &self.options.for_key(&key)) {
+                    Err(err) if self.skip_invalid && err.is_invalid_content() => {
+                        log_warn!("skipping invalid secret file {}: {}", path.display(), err);
+                        continue;
+                    }
+                    result => result?,
+                };
+                let (loaded, bytes) = loaded;
+                stats.record(bytes);
+                let resolved = self.options.resolve_path(&path).display().to_string();
+                let loaded =
+                    self.options
+                        .label(loaded, &key, &path.display().to_string(), &resolved);
+                m.insert(key, loaded);
+            }
+
+            if let Some(max) = self.max_keys {
+                if m.len() > max {
+                    return Err(SecretError::TooManyKeys { path: dir, max }.into());
+                }
+            }
+        }
+
+        self.stats.set(stats);
+        Ok(m)
+    }
+}
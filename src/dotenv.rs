@@ -0,0 +1,150 @@
+use std::path::PathBuf;
+
+use config::{ConfigError, Map, Source, Value, ValueKind};
+
+use crate::Options;
+
+/// Parses the content of a `.env` file into its variables, in order.
+///
+/// Each non-empty line not starting with `#` is a `KEY=value` assignment, optionally
+/// preceded by `export`. Values may be wrapped in single or double quotes, which are removed.
+/// No interpolation or escape sequence is processed.
+pub(crate) fn parse(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let key = key.trim();
+            if key.is_empty() {
+                return None;
+            }
+
+            Some((key.to_string(), unquote(value.trim()).to_string()))
+        })
+        .collect()
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            return &value[1..value.len() - 1];
+        }
+    }
+
+    value
+}
+
+/// A `.env` file used as the secret store itself, each `KEY=value` assignment being
+/// inserted as a string value.
+///
+/// Keys are lowercased like environment variables, so that `DB__PASSWORD=secret` with the
+/// `__` separator is inserted under `db.password`. See `DotenvVarProvider` to read the
+/// variables pointing to secret files from a `.env` file instead.
+#[derive(Clone, Debug)]
+pub struct DotenvSecret {
+    /// Path of the `.env` file to load.
+    path: PathBuf,
+
+    /// Optional prefix of the keys to load, removed from them along with the `_` following
+    /// it. Keys without the prefix are skipped.
+    prefix: Option<String>,
+
+    /// Optional character sequence that separates each key level, replaced with `.`.
+    separator: Option<String>,
+
+    /// Options controlling how the `.env` file is read. Its content is never parsed with a
+    /// format, so only the reading options apply.
+    options: Options,
+
+    /// Fail when the file does not exist, instead of inserting nothing. Defaults to `true`.
+    required: bool,
+}
+
+impl DotenvSecret {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            prefix: None,
+            separator: None,
+            options: Options::default(),
+            required: true,
+        }
+    }
+
+    pub fn prefix(mut self, s: &str) -> Self {
+        self.prefix = Some(s.into());
+        self
+    }
+
+    pub fn separator(mut self, s: &str) -> Self {
+        self.separator = Some(s.into());
+        self
+    }
+
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Returns the config key of the variable `name`, if it has the prefix.
+    fn key(&self, name: &str) -> Option<String> {
+        let mut key = name.to_lowercase();
+
+        if let Some(ref prefix) = self.prefix {
+            let prefix = format!("{}_", prefix).to_lowercase();
+            key = key.strip_prefix(&prefix)?.to_string();
+        }
+
+        match self.separator {
+            Some(ref separator) if !separator.is_empty() => {
+                Some(key.replace(&separator.to_lowercase(), "."))
+            }
+            _ => Some(key),
+        }
+    }
+}
+
+impl<P: Into<PathBuf>> From<P> for DotenvSecret {
+    fn from(path: P) -> Self {
+        Self::new(path)
+    }
+}
+
+impl Source for DotenvSecret {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new((*self).clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut m = Map::new();
+
+        let path = self.options.resolve_path(&self.path);
+        if !self.required && !self.options.exists(&path) {
+            return Ok(m);
+        }
+
+        let bytes = self.options.read_bytes(&path)?;
+        let text = self.options.decode(&path, bytes)?;
+
+        let uri = path.display().to_string();
+        for (name, value) in parse(&text) {
+            if let Some(key) = self.key(&name) {
+                let value = Value::new(Some(&uri), ValueKind::String(value));
+                let value = self.options.label(value, &key, &uri, &uri);
+                m.insert(key, value);
+            }
+        }
+
+        Ok(m)
+    }
+}
@@ -0,0 +1,234 @@
+use std::borrow::Cow;
+
+use config::FileFormat;
+
+/// Handling of keys appearing more than once in a table of a secret file.
+///
+/// Parsers usually keep the last occurrence silently, which could hide a second
+/// `admin_token` slipped into a secret file. Duplicates are only detected where the format
+/// exposes them:
+///
+/// - JSON files parsed by this crate support every policy,
+/// - YAML files parsed by this crate support `Error` and `Last`, `First` failing on
+///   duplicates like `Error` does,
+/// - TOML files are always rejected by their parser when a key is duplicated,
+/// - other formats, and files handed over to `config::File` because the format is not
+///   enabled on this crate, keep the behavior of their parser.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Fail to load the secret file.
+    Error,
+
+    /// Keep the first occurrence of the key.
+    First,
+
+    /// Keep the last occurrence of the key.
+    Last,
+}
+
+#[allow(clippy::derivable_impls)] // `#[default]` on variants requires Rust 1.62
+impl Default for DuplicatePolicy {
+    fn default() -> Self {
+        DuplicatePolicy::Error
+    }
+}
+
+/// Applies `policy` to the duplicate keys of `text`, parsed as `format`.
+///
+/// Returns the text to parse, or the first duplicated key when they are an error. Text that
+/// cannot be parsed is returned as is, for the parser to report why.
+#[allow(unused_variables)]
+pub(crate) fn apply(
+    format: Option<FileFormat>,
+    text: &str,
+    policy: DuplicatePolicy,
+) -> Result<Cow<'_, str>, String> {
+    if policy == DuplicatePolicy::Last {
+        return Ok(Cow::Borrowed(text));
+    }
+
+    match format {
+        #[cfg(feature = "json")]
+        Some(FileFormat::Json) => json::apply(text, policy),
+        #[cfg(feature = "yaml")]
+        Some(FileFormat::Yaml) => yaml::check(text).map(|_| Cow::Borrowed(text)),
+        _ => Ok(Cow::Borrowed(text)),
+    }
+}
+
+#[cfg(feature = "json")]
+mod json {
+    use std::{borrow::Cow, cell::RefCell, fmt};
+
+    use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+    use serde_json::{Map, Number, Value};
+
+    use super::DuplicatePolicy;
+
+    pub(super) fn apply(text: &str, policy: DuplicatePolicy) -> Result<Cow<'_, str>, String> {
+        let duplicate = RefCell::new(None);
+        let seed = Dedup {
+            policy,
+            duplicate: &duplicate,
+        };
+
+        let mut deserializer = serde_json::Deserializer::from_str(text);
+        let value = seed.deserialize(&mut deserializer);
+
+        match (duplicate.into_inner(), value) {
+            (Some(key), _) if policy == DuplicatePolicy::Error => Err(key),
+            (Some(_), Ok(value)) => Ok(Cow::Owned(value.to_string())),
+            _ => Ok(Cow::Borrowed(text)),
+        }
+    }
+
+    /// Builds a JSON value keeping the first occurrence of duplicate keys, or failing on
+    /// them, the first duplicated key being recorded.
+    #[derive(Clone, Copy)]
+    struct Dedup<'a> {
+        policy: DuplicatePolicy,
+        duplicate: &'a RefCell<Option<String>>,
+    }
+
+    impl<'de, 'a> DeserializeSeed<'de> for Dedup<'a> {
+        type Value = Value;
+
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+            deserializer.deserialize_any(self)
+        }
+    }
+
+    impl<'de, 'a> Visitor<'de> for Dedup<'a> {
+        type Value = Value;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a JSON value")
+        }
+
+        fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+            Ok(Value::Bool(v))
+        }
+
+        fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+            Ok(Value::Number(v.into()))
+        }
+
+        fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+            Ok(Value::Number(v.into()))
+        }
+
+        fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+            Ok(Number::from_f64(v).map_or(Value::Null, Value::Number))
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+            Ok(Value::String(v.to_string()))
+        }
+
+        fn visit_unit<E>(self) -> Result<Value, E> {
+            Ok(Value::Null)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+            let mut values = Vec::new();
+            while let Some(value) = seq.next_element_seed(self)? {
+                values.push(value);
+            }
+            Ok(Value::Array(values))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+            let mut values = Map::new();
+            while let Some(key) = map.next_key::<String>()? {
+                let value = map.next_value_seed(self)?;
+                if values.contains_key(&key) {
+                    self.duplicate
+                        .borrow_mut()
+                        .get_or_insert_with(|| key.clone());
+                    if self.policy == DuplicatePolicy::Error {
+                        return Err(de::Error::custom(format!("duplicate key `{}`", key)));
+                    }
+                    continue;
+                }
+                values.insert(key, value);
+            }
+            Ok(Value::Object(values))
+        }
+    }
+}
+
+#[cfg(feature = "yaml")]
+mod yaml {
+    use std::collections::HashSet;
+
+    use yaml_rust::{
+        parser::{EventReceiver, Parser},
+        Event,
+    };
+
+    /// Fails with the first key repeated in a mapping of the YAML `text`.
+    pub(super) fn check(text: &str) -> Result<(), String> {
+        let mut detector = Detector::default();
+        // Invalid documents are reported by the parser of the secret file
+        let _ = Parser::new(text.chars()).load(&mut detector, true);
+
+        match detector.duplicate {
+            Some(key) => Err(key),
+            None => Ok(()),
+        }
+    }
+
+    /// Collection being parsed.
+    enum Frame {
+        Sequence,
+
+        /// Mapping with its scalar keys, `key` telling whether the next node is a key.
+        Mapping {
+            keys: HashSet<String>,
+            key: bool,
+        },
+    }
+
+    #[derive(Default)]
+    struct Detector {
+        stack: Vec<Frame>,
+        duplicate: Option<String>,
+    }
+
+    impl Detector {
+        /// Records a complete node, `scalar` giving its value when it is a scalar.
+        fn node(&mut self, scalar: Option<String>) {
+            if let Some(Frame::Mapping { keys, key }) = self.stack.last_mut() {
+                if *key {
+                    if let Some(scalar) = scalar {
+                        if keys.contains(&scalar) {
+                            self.duplicate.get_or_insert(scalar);
+                        } else {
+                            keys.insert(scalar);
+                        }
+                    }
+                }
+                *key = !*key;
+            }
+        }
+    }
+
+    impl EventReceiver for Detector {
+        fn on_event(&mut self, event: Event) {
+            match event {
+                Event::Scalar(value, ..) => self.node(Some(value)),
+                Event::Alias(_) => self.node(None),
+                Event::SequenceStart(_) => self.stack.push(Frame::Sequence),
+                Event::MappingStart(_) => self.stack.push(Frame::Mapping {
+                    keys: HashSet::new(),
+                    key: true,
+                }),
+                Event::SequenceEnd | Event::MappingEnd => {
+                    self.stack.pop();
+                    self.node(None);
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,17 @@
+/// Handling of the secret files that are zero bytes long.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmptyFilePolicy {
+    /// Load the file like any other, an empty secret usually parsing as an empty table.
+    Load,
+
+    /// Fail with `SecretError::EmptyFile`, since an empty secret usually means that the step
+    /// generating it silently produced nothing.
+    Error,
+}
+
+#[allow(clippy::derivable_impls)] // `#[default]` on variants requires Rust 1.62
+impl Default for EmptyFilePolicy {
+    fn default() -> Self {
+        EmptyFilePolicy::Load
+    }
+}
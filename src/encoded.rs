@@ -0,0 +1,78 @@
+use std::{fmt, path::Path};
+
+use base64::{engine::general_purpose, Engine};
+
+use crate::SecretError;
+
+/// Alphabet and padding of base64 encoded secret files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base64Variant {
+    /// The standard alphabet with `+` and `/`, padded with `=`.
+    Standard,
+
+    /// The URL-safe alphabet with `-` and `_`, padded with `=`.
+    UrlSafe,
+
+    /// The URL-safe alphabet without padding, as used by JWTs.
+    UrlSafeNoPad,
+}
+
+#[allow(clippy::derivable_impls)] // `#[default]` on variants requires Rust 1.62
+impl Default for Base64Variant {
+    fn default() -> Self {
+        Base64Variant::Standard
+    }
+}
+
+impl fmt::Display for Base64Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Base64Variant::Standard => "standard",
+            Base64Variant::UrlSafe => "URL-safe",
+            Base64Variant::UrlSafeNoPad => "unpadded URL-safe",
+        })
+    }
+}
+
+impl Base64Variant {
+    const ALL: [Base64Variant; 3] = [
+        Base64Variant::Standard,
+        Base64Variant::UrlSafe,
+        Base64Variant::UrlSafeNoPad,
+    ];
+
+    fn decode(&self, encoded: &[u8]) -> Option<Vec<u8>> {
+        let engine = match self {
+            Base64Variant::Standard => &general_purpose::STANDARD,
+            Base64Variant::UrlSafe => &general_purpose::URL_SAFE,
+            Base64Variant::UrlSafeNoPad => &general_purpose::URL_SAFE_NO_PAD,
+        };
+        engine.decode(encoded).ok()
+    }
+}
+
+/// Decodes the base64 content of the secret file at `path`, surrounding whitespace ignored.
+///
+/// On failure, the error names the variant that would decode the content, if any, but never
+/// the content itself.
+pub(crate) fn decode_base64(
+    path: &Path,
+    bytes: &[u8],
+    variant: Base64Variant,
+) -> Result<Vec<u8>, SecretError> {
+    let start = bytes.iter().position(|b| !b.is_ascii_whitespace());
+    let end = bytes.iter().rposition(|b| !b.is_ascii_whitespace());
+    let encoded = match (start, end) {
+        (Some(start), Some(end)) => &bytes[start..=end],
+        _ => &[],
+    };
+
+    variant.decode(encoded).ok_or_else(|| SecretError::Base64 {
+        path: path.to_path_buf(),
+        variant,
+        detected: Base64Variant::ALL
+            .iter()
+            .copied()
+            .find(|other| other.decode(encoded).is_some()),
+    })
+}
@@ -0,0 +1,46 @@
+use std::borrow::Cow;
+
+/// Text encoding of a secret file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8, with or without a byte order mark.
+    Utf8,
+
+    /// UTF-16 little endian, with or without a byte order mark.
+    Utf16Le,
+
+    /// UTF-16 big endian, with or without a byte order mark.
+    Utf16Be,
+
+    /// Detect the encoding from the byte order mark, falling back to UTF-8.
+    Auto,
+}
+
+#[allow(clippy::derivable_impls)] // `#[default]` on variants requires Rust 1.62
+impl Default for Encoding {
+    fn default() -> Self {
+        Encoding::Utf8
+    }
+}
+
+impl Encoding {
+    /// Decodes `bytes` to UTF-8, returning `None` if they are malformed.
+    pub(crate) fn decode(self, bytes: &[u8]) -> Option<Cow<'_, str>> {
+        let encoding = match self {
+            Encoding::Utf8 => encoding_rs::UTF_8,
+            Encoding::Utf16Le => encoding_rs::UTF_16LE,
+            Encoding::Utf16Be => encoding_rs::UTF_16BE,
+            Encoding::Auto => match encoding_rs::Encoding::for_bom(bytes) {
+                Some((encoding, _)) => encoding,
+                None => encoding_rs::UTF_8,
+            },
+        };
+
+        let bytes = match encoding_rs::Encoding::for_bom(bytes) {
+            Some((bom, len)) if bom == encoding => &bytes[len..],
+            _ => bytes,
+        };
+
+        encoding.decode_without_bom_handling_and_without_replacement(bytes)
+    }
+}
@@ -0,0 +1,375 @@
+use std::{error::Error, fmt, io, path::PathBuf};
+
+use config::ConfigError;
+
+/// Errors raised while loading a secret.
+#[derive(Debug)]
+pub enum SecretError {
+    /// The secret file could not be read or parsed.
+    Config(ConfigError),
+
+    /// The secret file could not be read.
+    Read { path: PathBuf, source: io::Error },
+
+    /// The secret file could not be decrypted, `reason` never including its content.
+    Decrypt { path: PathBuf, reason: String },
+
+    /// The secret file could not be decompressed.
+    Decompress { path: PathBuf, source: io::Error },
+
+    /// The secret file is empty while `EmptyFilePolicy::Error` is set.
+    EmptyFile { path: PathBuf },
+
+    /// The content of the secret file is not valid in the expected encoding.
+    Decode { path: PathBuf },
+
+    /// The content of the secret file is not valid base64 of the expected variant, `detected`
+    /// being a variant that decodes it.
+    #[cfg(feature = "binary")]
+    Base64 {
+        path: PathBuf,
+        variant: crate::Base64Variant,
+        detected: Option<crate::Base64Variant>,
+    },
+
+    /// The secret file needs to be parsed by this crate but no format is available for it.
+    UnsupportedFormat { path: PathBuf },
+
+    /// The format of the secret file cannot be inferred from its extension.
+    UnknownFormat {
+        path: PathBuf,
+        extension: Option<String>,
+    },
+
+    /// None of the formats of the format chain could parse the secret file.
+    NoFormatMatched {
+        path: PathBuf,
+        attempts: Vec<String>,
+    },
+
+    /// A variable refers to another variable which is not set.
+    UnresolvedReference { var: String, target: String },
+
+    /// A key appears more than once in a table of the secret file.
+    DuplicateKey { path: PathBuf, key: String },
+
+    /// A `${VAR}` placeholder of a rendered secret file refers to a variable which is not set.
+    UnresolvedPlaceholder { path: PathBuf, var: String },
+
+    /// Variables refer to each other in a loop, `chain` listing them up to the repeated one.
+    ReferenceCycle { chain: Vec<String> },
+
+    /// A secret could not be fetched from a secret manager.
+    Remote { resource: String, reason: String },
+
+    /// None of the candidate paths of a secret file exists.
+    NoCandidate {
+        key: String,
+        candidates: Vec<PathBuf>,
+    },
+
+    /// A member of an archive could not be loaded.
+    Member {
+        archive: PathBuf,
+        member: String,
+        source: Box<SecretError>,
+    },
+
+    /// The secret file pointed by an environment variable could not be loaded.
+    Var {
+        var: String,
+        path: PathBuf,
+        source: Box<SecretError>,
+    },
+
+    /// The secret file loaded at the root has no scalar field to derive its key from.
+    KeyField {
+        var: String,
+        path: PathBuf,
+        field: String,
+    },
+
+    /// Required config keys were not produced, each listed with the variables that would
+    /// provide it.
+    MissingKeys { missing: Vec<(String, Vec<String>)> },
+
+    /// A source required to load secrets produced none.
+    NoSecrets,
+
+    /// The directory at `path` produced more keys than the `max` allowed.
+    TooManyKeys { path: PathBuf, max: usize },
+
+    /// The inline content of an environment variable could not be parsed.
+    Inline {
+        var: String,
+        source: Box<SecretError>,
+    },
+
+    /// The manifest variable is not a JSON object of paths, `reason` giving the position of
+    /// the error but never the content of the variable.
+    #[cfg(feature = "json")]
+    Manifest { var: String, reason: String },
+
+    /// The variable selecting the subdirectory of a `DirectorySecret` is not set and there is
+    /// no default subdirectory.
+    UnsetSwitch { var: String },
+
+    /// The options of a source contradict each other.
+    InvalidOptions(String),
+
+    /// The directory of the current executable could not be determined.
+    CurrentExe(io::Error),
+
+    /// A credential could not be read from the credential store.
+    #[cfg(feature = "keyring")]
+    Keyring {
+        service: String,
+        user: String,
+        source: keyring::Error,
+    },
+}
+
+impl SecretError {
+    /// Whether the error comes from the content of a secret file rather than from reading it.
+    pub(crate) fn is_invalid_content(&self) -> bool {
+        matches!(
+            self,
+            SecretError::Config(ConfigError::FileParse { .. })
+                | SecretError::Decode { .. }
+                | SecretError::NoFormatMatched { .. }
+                | SecretError::DuplicateKey { .. }
+        )
+    }
+}
+
+impl fmt::Display for SecretError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretError::Config(err) => err.fmt(f),
+            SecretError::Read { path, source } => {
+                write!(
+                    f,
+                    "failed to read secret file {}: {}",
+                    path.display(),
+                    source
+                )
+            }
+            SecretError::Decrypt { path, reason } => write!(
+                f,
+                "failed to decrypt secret file {}: {}",
+                path.display(),
+                reason
+            ),
+            SecretError::Decompress { path, source } => write!(
+                f,
+                "failed to decompress secret file {}: {}",
+                path.display(),
+                source
+            ),
+            SecretError::EmptyFile { path } => {
+                write!(f, "secret file {} is empty", path.display())
+            }
+            SecretError::Decode { path } => {
+                write!(
+                    f,
+                    "secret file {} is not valid text in the configured encoding",
+                    path.display()
+                )
+            }
+            #[cfg(feature = "binary")]
+            SecretError::Base64 {
+                path,
+                variant,
+                detected,
+            } => {
+                write!(
+                    f,
+                    "secret file {} is not valid {} base64",
+                    path.display(),
+                    variant
+                )?;
+                match detected {
+                    Some(detected) => write!(
+                        f,
+                        ", it is {} base64, set it with `base64_variant(...)`",
+                        detected
+                    ),
+                    None => Ok(()),
+                }
+            }
+            SecretError::UnsupportedFormat { path } => write!(
+                f,
+                "no format available to parse secret file {}, enable the feature of its format",
+                path.display()
+            ),
+            SecretError::UnknownFormat { path, extension } => match extension {
+                Some(extension) => write!(
+                    f,
+                    "unknown extension `{}` for secret file {}, set its format with `format(...)`",
+                    extension,
+                    path.display()
+                ),
+                None => write!(
+                    f,
+                    "cannot infer the format of secret file {} which has no extension, set it \
+                     with `format(...)`, or read it as a string with `raw(true)`",
+                    path.display()
+                ),
+            },
+            SecretError::NoFormatMatched { path, attempts } => write!(
+                f,
+                "no format could parse secret file {}, tried {}",
+                path.display(),
+                attempts.join("; ")
+            ),
+            SecretError::UnresolvedReference { var, target } => write!(
+                f,
+                "environment variable {} refers to {} which is not set",
+                var, target
+            ),
+            SecretError::DuplicateKey { path, key } => write!(
+                f,
+                "secret file {} contains the key `{}` more than once",
+                path.display(),
+                key
+            ),
+            SecretError::UnresolvedPlaceholder { path, var } => write!(
+                f,
+                "secret file {} refers to environment variable {} which is not set",
+                path.display(),
+                var
+            ),
+            SecretError::ReferenceCycle { chain } => write!(
+                f,
+                "environment variables refer to each other in a cycle: {}",
+                chain.join(" -> ")
+            ),
+            SecretError::Remote { resource, reason } => {
+                write!(f, "failed to fetch secret {}: {}", resource, reason)
+            }
+            SecretError::NoCandidate { key, candidates } => {
+                let candidates: Vec<_> = candidates
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                write!(
+                    f,
+                    "no secret file found for `{}`, tried {}",
+                    key,
+                    candidates.join(", ")
+                )
+            }
+            SecretError::Member {
+                archive,
+                member,
+                source,
+            } => write!(
+                f,
+                "failed to load member {} of archive {}: {}",
+                member,
+                archive.display(),
+                source
+            ),
+            SecretError::Var { var, path, source } => write!(
+                f,
+                "failed to load secret file {} of environment variable {}: {}",
+                path.display(),
+                var,
+                source
+            ),
+            SecretError::KeyField { var, path, field } => write!(
+                f,
+                "secret file {} of environment variable {} has no scalar field `{}` to derive its \
+                 key from",
+                path.display(),
+                var,
+                field
+            ),
+            SecretError::Inline { var, source } => write!(
+                f,
+                "failed to parse the inline content of environment variable {}: {}",
+                var, source
+            ),
+            SecretError::MissingKeys { missing } => {
+                let missing: Vec<_> = missing
+                    .iter()
+                    .map(|(key, vars)| match vars.len() {
+                        0 => format!("`{}`", key),
+                        _ => format!("`{}`, set `{}`", key, vars.join("` or `")),
+                    })
+                    .collect();
+                write!(f, "missing required secrets: {}", missing.join("; "))
+            }
+            SecretError::NoSecrets => write!(
+                f,
+                "no secret was loaded, check the prefix and suffix of the secret source"
+            ),
+            SecretError::TooManyKeys { path, max } => write!(
+                f,
+                "secret directory {} contains more than {} secrets, check that it is mounted at \
+                 the right place",
+                path.display(),
+                max
+            ),
+            #[cfg(feature = "json")]
+            SecretError::Manifest { var, reason } => write!(
+                f,
+                "environment variable {} is not a valid manifest of secret files: {}",
+                var, reason
+            ),
+            SecretError::UnsetSwitch { var } => write!(
+                f,
+                "environment variable {} selecting the secret directory is not set",
+                var
+            ),
+            SecretError::InvalidOptions(reason) => write!(f, "invalid secret source: {}", reason),
+            SecretError::CurrentExe(source) => write!(
+                f,
+                "failed to locate the directory of the current executable: {}",
+                source
+            ),
+            #[cfg(feature = "keyring")]
+            SecretError::Keyring {
+                service,
+                user,
+                source,
+            } => write!(
+                f,
+                "failed to read credential {} of {} from the credential store: {}",
+                user, service, source
+            ),
+        }
+    }
+}
+
+impl Error for SecretError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SecretError::Config(err) => Some(err),
+            SecretError::Read { source, .. } => Some(source),
+            SecretError::Decompress { source, .. } => Some(source),
+            SecretError::Member { source, .. } => Some(source.as_ref()),
+            SecretError::Var { source, .. } => Some(source.as_ref()),
+            SecretError::Inline { source, .. } => Some(source.as_ref()),
+            SecretError::CurrentExe(source) => Some(source),
+            #[cfg(feature = "keyring")]
+            SecretError::Keyring { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<ConfigError> for SecretError {
+    fn from(err: ConfigError) -> Self {
+        SecretError::Config(err)
+    }
+}
+
+impl From<SecretError> for ConfigError {
+    fn from(err: SecretError) -> Self {
+        match err {
+            SecretError::Config(err) => err,
+            err => ConfigError::Foreign(Box::new(err)),
+        }
+    }
+}
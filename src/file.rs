@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+
+use config::{ConfigError, Map, Source, Value};
+
+use crate::{load::load_counted, stats::StatsCell, CollectStats, Options, SecretError};
+
+#[derive(Clone, Debug)]
+pub struct FileSecret {
+    /// Config key under which the content of the file is inserted.
+    ///
+    /// Nested keys can be targeted using `.`, for example `redis.password`.
+    key: String,
+
+    /// Paths of the secret file to load, the first existing one being read.
+    ///
+    /// Several candidates let a secret live at a different place on each platform, such as
+    /// `/etc/app/db.json` on Linux and `C:\ProgramData\app\db.json` on Windows.
+    paths: Vec<PathBuf>,
+
+    /// Options controlling how the secret file is read.
+    options: Options,
+
+    /// Fail when no candidate exists, instead of inserting nothing. Defaults to `true`.
+    required: bool,
+
+    /// Statistics of the last successful `collect()`.
+    stats: StatsCell,
+}
+
+impl FileSecret {
+    pub fn new(key: &str, path: impl Into<PathBuf>) -> Self {
+        Self::with_candidates(key, vec![path.into()])
+    }
+
+    pub fn with_candidates(key: &str, paths: Vec<PathBuf>) -> Self {
+        Self {
+            key: key.into(),
+            paths,
+            options: Options::default(),
+            required: true,
+            stats: StatsCell::default(),
+        }
+    }
+
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Returns the number of files read and their total size during the last successful
+    /// `collect()`.
+    pub fn last_stats(&self) -> CollectStats {
+        self.stats.get()
+    }
+}
+
+impl Source for FileSecret {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new((*self).clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut m = Map::new();
+
+        let candidate = self
+            .paths
+            .iter()
+            .find(|path| self.options.exists(&self.options.resolve_path(path)));
+        let path = match candidate {
+            Some(path) => path,
+            None if !self.required => {
+                self.stats.set(CollectStats::default());
+                return Ok(m);
+            }
+            // A single path is read anyway, to report why it cannot be
+            None if self.paths.len() == 1 => &self.paths[0],
+            None => {
+                return Err(SecretError::NoCandidate {
+                    key: self.key.clone(),
+                    candidates: self.paths.clone(),
+                }
+                .into())
+            }
+        };
+
+        let (loaded, bytes) = load_counted(path, &self.options.for_key(&self.key))?;
+        let mut stats = CollectStats::default();
+        stats.record(bytes);
+        let resolved = self.options.resolve_path(path).display().to_string();
+        let value = path.display().to_string();
+        let loaded = self.options.label(loaded, &self.key, &value, &resolved);
+        m.insert(self.key.clone(), loaded);
+
+        self.stats.set(stats);
+        Ok(m)
+    }
+}
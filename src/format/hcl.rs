@@ -0,0 +1,70 @@
+use std::error::Error;
+
+use config::{FileStoredFormat, Format, Map, Value, ValueKind};
+
+/// HCL secret format.
+///
+/// Attributes become keys of their enclosing table and blocks become nested tables named
+/// after their type, each label adding a nesting level. Expressions must be literal values,
+/// as produced by Terraform outputs.
+///
+/// ```hcl
+/// server {
+///   host = "0.0.0.0"
+///   port = 5000
+/// }
+///
+/// redis {
+///   nodes = ["redis://10.0.0.1:6379", "redis://10.0.0.2:6379"]
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Hcl;
+
+impl Format for Hcl {
+    fn parse(
+        &self,
+        uri: Option<&String>,
+        text: &str,
+    ) -> Result<Map<String, Value>, Box<dyn Error + Send + Sync>> {
+        let value = hcl::from_str::<hcl::Value>(text)?;
+        match from_hcl(uri, value).kind {
+            ValueKind::Table(map) => Ok(map),
+            _ => Ok(Map::new()),
+        }
+    }
+}
+
+impl FileStoredFormat for Hcl {
+    fn file_extensions(&self) -> &'static [&'static str] {
+        &["hcl"]
+    }
+}
+
+fn from_hcl(uri: Option<&String>, value: hcl::Value) -> Value {
+    let kind = match value {
+        hcl::Value::Null => ValueKind::Nil,
+        hcl::Value::Bool(value) => ValueKind::Boolean(value),
+        hcl::Value::Number(number) => match number.as_i64() {
+            Some(value) => ValueKind::I64(value),
+            None => match number.as_u64() {
+                Some(value) => ValueKind::U64(value),
+                None => ValueKind::Float(number.as_f64().unwrap_or_default()),
+            },
+        },
+        hcl::Value::String(value) => ValueKind::String(value),
+        hcl::Value::Array(values) => ValueKind::Array(
+            values
+                .into_iter()
+                .map(|value| from_hcl(uri, value))
+                .collect(),
+        ),
+        hcl::Value::Object(map) => ValueKind::Table(
+            map.into_iter()
+                .map(|(key, value)| (key, from_hcl(uri, value)))
+                .collect(),
+        ),
+    };
+
+    Value::new(uri, kind)
+}
@@ -0,0 +1,180 @@
+#[cfg(feature = "hcl")]
+mod hcl;
+#[cfg(feature = "xml")]
+mod xml;
+
+#[cfg(feature = "hcl")]
+pub use self::hcl::Hcl;
+#[cfg(feature = "xml")]
+pub use xml::Xml;
+
+use std::path::Path;
+
+use config::{ConfigError, Format, Value, ValueKind};
+
+use crate::SecretError;
+
+/// A parser of secret files, registered for an extension with `Options::register_format`.
+///
+/// It receives the content of the file as read, decrypted and decoded from base64 if needed,
+/// and returns the value inserted for it, usually a table. The content is not transcoded nor
+/// rendered, so a format reading text handles the encoding and byte order mark itself. The
+/// formats of this crate and of `config` implement it.
+pub trait SecretFormat: Send + Sync {
+    fn parse(&self, bytes: &[u8]) -> Result<Value, SecretError>;
+}
+
+/// Parses `bytes` as UTF-8 text in `format`, ignoring a leading byte order mark.
+fn parse_text(format: &dyn Format, bytes: &[u8]) -> Result<Value, SecretError> {
+    let text = std::str::from_utf8(bytes).map_err(|err| ConfigError::FileParse {
+        uri: None,
+        cause: Box::new(err),
+    })?;
+    let text = text.strip_prefix(crate::load::BOM).unwrap_or(text);
+    let map = format
+        .parse(None, text)
+        .map_err(|cause| ConfigError::FileParse { uri: None, cause })?;
+    Ok(Value::new(None, ValueKind::Table(map)))
+}
+
+impl SecretFormat for config::FileFormat {
+    fn parse(&self, bytes: &[u8]) -> Result<Value, SecretError> {
+        parse_text(self, bytes)
+    }
+}
+
+#[cfg(feature = "hcl")]
+impl SecretFormat for Hcl {
+    fn parse(&self, bytes: &[u8]) -> Result<Value, SecretError> {
+        parse_text(self, bytes)
+    }
+}
+
+#[cfg(feature = "xml")]
+impl SecretFormat for Xml {
+    fn parse(&self, bytes: &[u8]) -> Result<Value, SecretError> {
+        parse_text(self, bytes)
+    }
+}
+
+/// Returns the format able to parse the text of `path` according to its extension, if any.
+///
+/// This covers the formats provided by this crate and the formats of `config` enabled
+/// through the features of this crate. Files whose extension is not handled here can still
+/// be parsed by `config::File` when no decoding of their content is needed.
+pub(crate) fn for_path(path: &Path) -> Option<Box<dyn Format>> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+
+    match extension.as_str() {
+        #[cfg(feature = "json")]
+        "json" => Some(Box::new(config::FileFormat::Json)),
+        #[cfg(feature = "yaml")]
+        "yaml" | "yml" => Some(Box::new(config::FileFormat::Yaml)),
+        #[cfg(feature = "toml")]
+        "toml" => Some(Box::new(config::FileFormat::Toml)),
+        #[cfg(feature = "ini")]
+        "ini" => Some(Box::new(config::FileFormat::Ini)),
+        #[cfg(feature = "ron")]
+        "ron" => Some(Box::new(config::FileFormat::Ron)),
+        #[cfg(feature = "json5")]
+        "json5" => Some(Box::new(config::FileFormat::Json5)),
+        #[cfg(feature = "hcl")]
+        "hcl" => Some(Box::new(Hcl)),
+        #[cfg(feature = "xml")]
+        "xml" => Some(Box::new(Xml)),
+        _ => None,
+    }
+}
+
+/// Returns the `config` format matching the extension of `path`, if enabled on this crate.
+pub(crate) fn file_format(path: &Path) -> Option<config::FileFormat> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+
+    match extension.as_str() {
+        #[cfg(feature = "json")]
+        "json" => Some(config::FileFormat::Json),
+        #[cfg(feature = "yaml")]
+        "yaml" | "yml" => Some(config::FileFormat::Yaml),
+        #[cfg(feature = "toml")]
+        "toml" => Some(config::FileFormat::Toml),
+        #[cfg(feature = "ini")]
+        "ini" => Some(config::FileFormat::Ini),
+        #[cfg(feature = "ron")]
+        "ron" => Some(config::FileFormat::Ron),
+        #[cfg(feature = "json5")]
+        "json5" => Some(config::FileFormat::Json5),
+        _ => None,
+    }
+}
+
+/// Extensions of the formats supported by `config`, enabled or not.
+const CONFIG_EXTENSIONS: &[&str] = &["toml", "json", "yaml", "yml", "ini", "ron", "json5"];
+
+/// Whether the extension of `path` belongs to a format of `config` or of this crate.
+pub(crate) fn is_known(path: &Path) -> bool {
+    let extension = match path.extension().and_then(|e| e.to_str()) {
+        Some(extension) => extension.to_lowercase(),
+        None => return false,
+    };
+
+    CONFIG_EXTENSIONS.contains(&extension.as_str()) || for_path(path).is_some()
+}
+
+/// Key under which the root of a document is nested to parse roots that are not tables.
+const ROOT_KEY: &str = "config_secret_root";
+
+/// Parses the content of the secret file at `path` with `format`, reporting failures the
+/// same way `config::File` does.
+///
+/// Formats usually drop a root that is not a table. For JSON, JSON5 and YAML documents,
+/// an array or scalar root is kept by parsing the document again nested under a key.
+pub(crate) fn parse(
+    path: &Path,
+    format: &dyn Format,
+    uri: &str,
+    text: &str,
+) -> Result<Value, ConfigError> {
+    let uri = Some(uri.to_string());
+    let parse = |text: &str| {
+        format
+            .parse(uri.as_ref(), text)
+            .map_err(|cause| ConfigError::FileParse {
+                uri: uri.clone(),
+                cause,
+            })
+    };
+
+    let map = parse(text)?;
+
+    if map.is_empty() && !text.trim().is_empty() {
+        if let Some(wrapped) = wrap_root(path, text) {
+            if let Some(value) = parse(&wrapped)?.remove(ROOT_KEY) {
+                match value.kind {
+                    ValueKind::Table(_) | ValueKind::Nil => {}
+                    _ => return Ok(value),
+                }
+            }
+        }
+    }
+
+    Ok(Value::new(uri.as_ref(), ValueKind::Table(map)))
+}
+
+/// Nests the root of a document under `ROOT_KEY`, for the formats where it is possible.
+fn wrap_root(path: &Path, text: &str) -> Option<String> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+
+    match extension.as_str() {
+        "json" | "json5" => Some(format!("{{\"{}\": {}}}", ROOT_KEY, text)),
+        "yaml" | "yml" => {
+            let mut wrapped = format!("{}:\n", ROOT_KEY);
+            for line in text.lines() {
+                wrapped.push_str("  ");
+                wrapped.push_str(line);
+                wrapped.push('\n');
+            }
+            Some(wrapped)
+        }
+        _ => None,
+    }
+}
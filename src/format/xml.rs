@@ -0,0 +1,108 @@
+use std::error::Error;
+
+use config::{FileStoredFormat, Format, Map, Value, ValueKind};
+use roxmltree::{Document, Node};
+
+/// XML secret format.
+///
+/// The root element of the document is the table of the secret, its name is ignored.
+/// Inside it:
+///
+/// - each child element becomes a key named after its tag,
+/// - an element containing only text becomes a string,
+/// - an element with attributes or child elements becomes a table, its attributes becoming
+///   string entries and its text, if any, being stored under the `text` key,
+/// - sibling elements sharing the same tag become an array, in document order.
+///
+/// ```xml
+/// <config>
+///     <server host="0.0.0.0">
+///         <port>5000</port>
+///     </server>
+///     <redis>
+///         <nodes>redis://10.0.0.1:6379</nodes>
+///         <nodes>redis://10.0.0.2:6379</nodes>
+///     </redis>
+/// </config>
+/// ```
+///
+/// Since a single element cannot be told apart from a one-item list, a list field must
+/// contain at least two elements to be deserialized as such.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Xml;
+
+impl Format for Xml {
+    fn parse(
+        &self,
+        uri: Option<&String>,
+        text: &str,
+    ) -> Result<Map<String, Value>, Box<dyn Error + Send + Sync>> {
+        let document = Document::parse(text)?;
+        match from_element(uri, document.root_element()).kind {
+            ValueKind::Table(map) => Ok(map),
+            _ => Ok(Map::new()),
+        }
+    }
+}
+
+impl FileStoredFormat for Xml {
+    fn file_extensions(&self) -> &'static [&'static str] {
+        &["xml"]
+    }
+}
+
+fn from_element(uri: Option<&String>, node: Node) -> Value {
+    let has_children = node.children().any(|child| child.is_element());
+    let text = node
+        .children()
+        .filter(|child| child.is_text())
+        .filter_map(|child| child.text())
+        .collect::<String>();
+    let text = text.trim();
+
+    if !has_children && node.attributes().len() == 0 {
+        return Value::new(uri, ValueKind::String(text.to_string()));
+    }
+
+    let mut map = Map::new();
+
+    for attribute in node.attributes() {
+        map.insert(
+            attribute.name().to_string(),
+            Value::new(uri, ValueKind::String(attribute.value().to_string())),
+        );
+    }
+
+    if !text.is_empty() {
+        map.insert(
+            "text".to_string(),
+            Value::new(uri, ValueKind::String(text.to_string())),
+        );
+    }
+
+    for child in node.children().filter(|child| child.is_element()) {
+        let key = child.tag_name().name().to_string();
+        let value = from_element(uri, child);
+
+        match map.remove(&key) {
+            Some(Value {
+                kind: ValueKind::Array(mut values),
+                ..
+            }) => {
+                values.push(value);
+                map.insert(key, Value::new(uri, ValueKind::Array(values)));
+            }
+            Some(previous) => {
+                map.insert(
+                    key,
+                    Value::new(uri, ValueKind::Array(vec![previous, value])),
+                );
+            }
+            None => {
+                map.insert(key, value);
+            }
+        }
+    }
+
+    Value::new(uri, ValueKind::Table(map))
+}
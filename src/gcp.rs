@@ -0,0 +1,265 @@
+use std::{
+    env, fmt, fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::Engine;
+use config::{ConfigError, Map, Source, Value};
+use serde_json::Value as Json;
+
+use crate::{
+    cloud::{field, metadata, payload_value, remote_error, send},
+    SecretError,
+};
+
+const ENDPOINT: &str = "https://secretmanager.googleapis.com";
+const SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+const TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+const METADATA_TOKEN_URI: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+
+/// Source reading a secret version from GCP Secret Manager.
+///
+/// The payload is inserted under the config key, as a table when it is a JSON object and as a
+/// string otherwise. Requests are authenticated with the application default credentials:
+/// the file of `GOOGLE_APPLICATION_CREDENTIALS`, the file written by
+/// `gcloud auth application-default login`, or the metadata server of the instance.
+#[derive(Clone)]
+pub struct GcpSecretManagerSource {
+    /// Config key under which the payload is inserted.
+    ///
+    /// Nested keys can be targeted using `.`, for example `redis.password`.
+    key: String,
+
+    /// Resource name of the secret version, such as
+    /// `projects/my-project/secrets/redis/versions/3`.
+    ///
+    /// A secret name without version reads the `latest` version.
+    name: String,
+
+    /// Base URL of the Secret Manager API, for emulators and proxies.
+    endpoint: String,
+
+    /// OAuth access token used instead of the application default credentials.
+    access_token: Option<String>,
+
+    /// Fail when the secret version does not exist instead of inserting nothing.
+    ///
+    /// Defaults to `true`.
+    required: bool,
+}
+
+impl GcpSecretManagerSource {
+    pub fn new(key: &str, name: &str) -> Self {
+        Self {
+            key: key.into(),
+            name: name.into(),
+            endpoint: ENDPOINT.into(),
+            access_token: None,
+            required: true,
+        }
+    }
+
+    pub fn endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.trim_end_matches('/').into();
+        self
+    }
+
+    pub fn access_token(mut self, token: &str) -> Self {
+        self.access_token = Some(token.into());
+        self
+    }
+
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Returns the resource name of the secret version to read.
+    fn version_name(&self) -> String {
+        if self.name.contains("/versions/") {
+            self.name.clone()
+        } else {
+            format!("{}/versions/latest", self.name)
+        }
+    }
+
+    /// Reads the payload of the secret version, `None` meaning that it does not exist.
+    fn payload(&self) -> Result<Option<String>, SecretError> {
+        let name = self.version_name();
+        let token = match self.access_token {
+            Some(ref token) => token.clone(),
+            None => default_token(&name)?,
+        };
+
+        let url = format!("{}/v1/{}:access", self.endpoint, name);
+        let request = ureq::get(&url).set("Authorization", &format!("Bearer {}", token));
+        let response = match send(&name, request, None)? {
+            Some(response) => response,
+            None if !self.required => return Ok(None),
+            None => return Err(remote_error(&name, "secret version not found")),
+        };
+
+        let data = response
+            .get("payload")
+            .and_then(|payload| field(payload, "data"))
+            .ok_or_else(|| remote_error(&name, "response without payload"))?;
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(|err| remote_error(&name, err))?;
+
+        String::from_utf8(bytes)
+            .map(Some)
+            .map_err(|_| remote_error(&name, "payload is not valid UTF-8"))
+    }
+}
+
+/// Returns an access token from the application default credentials.
+fn default_token(resource: &str) -> Result<String, SecretError> {
+    if let Some(path) = credentials_file() {
+        let text = fs::read_to_string(&path).map_err(|source| SecretError::Read {
+            path: path.clone(),
+            source,
+        })?;
+        let credentials: Json = serde_json::from_str(&text).map_err(|err| {
+            remote_error(
+                resource,
+                format!("invalid credentials {}: {}", path.display(), err),
+            )
+        })?;
+
+        return match field(&credentials, "type").as_deref() {
+            Some("authorized_user") => user_token(resource, &credentials),
+            Some("service_account") => service_account_token(resource, &credentials),
+            other => Err(remote_error(
+                resource,
+                format!("unsupported credentials type {:?}", other),
+            )),
+        };
+    }
+
+    let request = ureq::get(METADATA_TOKEN_URI).set("Metadata-Flavor", "Google");
+    metadata(request)
+        .and_then(|response| field(&response, "access_token"))
+        .ok_or_else(|| remote_error(resource, "no application default credentials found"))
+}
+
+/// Returns the path of the application default credentials file, if any.
+fn credentials_file() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("GOOGLE_APPLICATION_CREDENTIALS") {
+        return Some(path.into());
+    }
+
+    let config_dir = if cfg!(windows) {
+        PathBuf::from(env::var_os("APPDATA")?)
+    } else {
+        PathBuf::from(env::var_os("HOME")?).join(".config")
+    };
+
+    let path = config_dir.join("gcloud/application_default_credentials.json");
+    if path.is_file() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Exchanges the refresh token of user credentials for an access token.
+fn user_token(resource: &str, credentials: &Json) -> Result<String, SecretError> {
+    let get = |name| {
+        field(credentials, name)
+            .ok_or_else(|| remote_error(resource, format!("credentials without {}", name)))
+    };
+    let (client_id, client_secret, refresh_token) = (
+        get("client_id")?,
+        get("client_secret")?,
+        get("refresh_token")?,
+    );
+
+    let form = [
+        ("grant_type", "refresh_token"),
+        ("client_id", client_id.as_str()),
+        ("client_secret", client_secret.as_str()),
+        ("refresh_token", refresh_token.as_str()),
+    ];
+    token_response(resource, ureq::post(TOKEN_URI), &form)
+}
+
+/// Exchanges a JWT signed with the key of a service account for an access token.
+fn service_account_token(resource: &str, credentials: &Json) -> Result<String, SecretError> {
+    let get = |name| {
+        field(credentials, name)
+            .ok_or_else(|| remote_error(resource, format!("credentials without {}", name)))
+    };
+    let (client_email, private_key) = (get("client_email")?, get("private_key")?);
+    let token_uri = field(credentials, "token_uri").unwrap_or_else(|| TOKEN_URI.into());
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let claims = serde_json::json!({
+        "iss": client_email,
+        "scope": SCOPE,
+        "aud": token_uri,
+        "iat": now,
+        "exp": now + 3600,
+    });
+
+    let key = jsonwebtoken::EncodingKey::from_rsa_pem(private_key.as_bytes())
+        .map_err(|err| remote_error(resource, err))?;
+    let header = jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256);
+    let assertion =
+        jsonwebtoken::encode(&header, &claims, &key).map_err(|err| remote_error(resource, err))?;
+
+    let form = [
+        ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+        ("assertion", assertion.as_str()),
+    ];
+    token_response(resource, ureq::post(&token_uri), &form)
+}
+
+/// Posts `form` to a token endpoint and returns the access token of the response.
+fn token_response(
+    resource: &str,
+    request: ureq::Request,
+    form: &[(&str, &str)],
+) -> Result<String, SecretError> {
+    send(resource, request, Some(form))?
+        .and_then(|response| field(&response, "access_token"))
+        .ok_or_else(|| remote_error(resource, "token endpoint returned no access token"))
+}
+
+// The access token is a credential, only whether it is set is shown
+impl fmt::Debug for GcpSecretManagerSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GcpSecretManagerSource")
+            .field("key", &self.key)
+            .field("name", &self.name)
+            .field("endpoint", &self.endpoint)
+            .field(
+                "access_token",
+                &self.access_token.as_ref().map(|_| "<redacted>"),
+            )
+            .field("required", &self.required)
+            .finish()
+    }
+}
+
+impl Source for GcpSecretManagerSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new((*self).clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut m = Map::new();
+
+        if let Some(payload) = self.payload()? {
+            let uri = format!("gcp:{}", self.version_name());
+            m.insert(self.key.clone(), payload_value(&uri, payload));
+        }
+
+        Ok(m)
+    }
+}
@@ -0,0 +1,33 @@
+use std::{fmt, ops::Deref, sync::Arc};
+
+/// A user provided callback shared between the clones of a source.
+///
+/// Callbacks cannot be compared or printed, so this wrapper gives them the `Clone` and
+/// `Debug` implementations that sources are expected to derive.
+pub(crate) struct Hook<F: ?Sized>(Arc<F>);
+
+impl<F: ?Sized> Hook<F> {
+    pub(crate) fn new(f: Arc<F>) -> Self {
+        Self(f)
+    }
+}
+
+impl<F: ?Sized> Clone for Hook<F> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for Hook<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Hook")
+    }
+}
+
+impl<F: ?Sized> Deref for Hook<F> {
+    type Target = F;
+
+    fn deref(&self) -> &F {
+        &self.0
+    }
+}
@@ -0,0 +1,83 @@
+use config::{ConfigError, Map, Source, Value, ValueKind};
+
+use crate::SecretError;
+
+/// Source reading a secret from the credential store of the operating system.
+///
+/// The password of the credential identified by `service` and `user` is inserted as a string
+/// under the config key, without touching the disk. The store is the macOS Keychain or the
+/// Windows Credential Manager.
+///
+/// On Linux, the store is the Secret Service of the session, such as GNOME Keyring or KWallet,
+/// reached over D-Bus and cached in the kernel keyutils, which alone would lose the credentials
+/// at reboot. The D-Bus library is built with the crate, so none needs to be installed.
+#[derive(Clone, Debug)]
+pub struct KeyringSecret {
+    /// Config key under which the password is inserted.
+    ///
+    /// Nested keys can be targeted using `.`, for example `redis.password`.
+    key: String,
+
+    /// Service of the credential.
+    service: String,
+
+    /// User of the credential.
+    user: String,
+
+    /// Fail when the credential does not exist instead of inserting nothing.
+    ///
+    /// Defaults to `true`.
+    required: bool,
+}
+
+impl KeyringSecret {
+    pub fn new(key: &str, service: &str, user: &str) -> Self {
+        Self {
+            key: key.into(),
+            service: service.into(),
+            user: user.into(),
+            required: true,
+        }
+    }
+
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Reads the password of the credential, `None` meaning that it does not exist.
+    fn password(&self) -> Result<Option<String>, SecretError> {
+        let error = |source| SecretError::Keyring {
+            service: self.service.clone(),
+            user: self.user.clone(),
+            source,
+        };
+
+        let entry = keyring::Entry::new(&self.service, &self.user).map_err(error)?;
+        match entry.get_password() {
+            Ok(password) => Ok(Some(password)),
+            Err(keyring::Error::NoEntry) if !self.required => Ok(None),
+            Err(err) => Err(error(err)),
+        }
+    }
+}
+
+impl Source for KeyringSecret {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new((*self).clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut m = Map::new();
+
+        if let Some(password) = self.password()? {
+            let uri = format!("keyring:{}:{}", self.service, self.user);
+            m.insert(
+                self.key.clone(),
+                Value::new(Some(&uri), ValueKind::String(password)),
+            );
+        }
+
+        Ok(m)
+    }
+}
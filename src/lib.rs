@@ -1,3 +1,90 @@
+/// Emits a warning when the `tracing` feature is enabled.
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::warn!($($arg)*);
+    };
+}
+
+#[cfg(feature = "tar")]
+mod archive;
+mod audit;
+#[cfg(feature = "azure")]
+mod azure;
+mod cache;
+mod case;
+#[cfg(any(feature = "azure", feature = "gcp"))]
+mod cloud;
+#[cfg(feature = "age")]
+mod decrypt;
+mod directory;
+mod dotenv;
+mod duplicate;
+mod empty;
+#[cfg(feature = "binary")]
+mod encoded;
+#[cfg(feature = "encoding")]
+mod encoding;
+mod error;
+mod file;
+mod format;
+#[cfg(feature = "gcp")]
+mod gcp;
+mod hook;
+#[cfg(feature = "keyring")]
+mod keyring;
+mod load;
+mod merge;
+mod newline;
+mod options;
+mod origin;
+mod position;
+mod reader;
+mod render;
 mod secret;
+mod stats;
+mod suffix;
+#[cfg(feature = "test-util")]
+mod test_util;
+#[cfg(feature = "tilde")]
+mod tilde;
+mod vars;
 
+#[cfg(feature = "tar")]
+pub use archive::TarSecret;
+pub use audit::AuditReport;
+#[cfg(feature = "azure")]
+pub use azure::AzureKeyVaultSource;
+pub use case::KeyCase;
+pub use directory::{DirectorySecret, SortOrder};
+pub use dotenv::DotenvSecret;
+pub use duplicate::DuplicatePolicy;
+pub use empty::EmptyFilePolicy;
+#[cfg(feature = "binary")]
+pub use encoded::Base64Variant;
+#[cfg(feature = "encoding")]
+pub use encoding::Encoding;
+pub use error::SecretError;
+pub use file::FileSecret;
+#[cfg(feature = "hcl")]
+pub use format::Hcl;
+pub use format::SecretFormat;
+#[cfg(feature = "xml")]
+pub use format::Xml;
+#[cfg(feature = "gcp")]
+pub use gcp::GcpSecretManagerSource;
+#[cfg(feature = "keyring")]
+pub use keyring::KeyringSecret;
+pub use load::{load_value, Options};
+pub use newline::NewlinePolicy;
+pub use options::SecretOptions;
+pub use origin::OriginMode;
+pub use position::Position;
+pub use reader::{FileReader, FsReader, MapReader};
+pub use render::UnresolvedPolicy;
 pub use secret::EnvironmentSecretFile;
+pub use stats::CollectStats;
+pub use suffix::SuffixRule;
+#[cfg(feature = "test-util")]
+pub use test_util::collect_into_map;
+pub use vars::{DotenvVarProvider, EnvVarProvider, MapVarProvider, VarProvider};
@@ -0,0 +1,868 @@
+use std::{
+    borrow::Cow,
+    env, io,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc},
+    thread,
+    time::Duration,
+};
+
+use config::{File, FileFormat, Format, Map, Source, Value, ValueKind};
+
+#[cfg(feature = "encoding")]
+use crate::Encoding;
+use crate::{
+    duplicate, format, hook::Hook, reader::FsReader, render, DuplicatePolicy, EmptyFilePolicy,
+    FileReader, KeyCase, NewlinePolicy, OriginMode, SecretError, SecretFormat, UnresolvedPolicy,
+    VarProvider,
+};
+
+/// Byte order mark that may start UTF-8 text.
+pub(crate) const BOM: char = '\u{feff}';
+
+/// Options controlling how a secret file is read and turned into a config value.
+///
+/// They are shared by every source of this crate so that a secret file is
+/// interpreted the same way whichever way it was discovered.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// Case applied to the keys produced by parsing a secret file, at every nesting level.
+    ///
+    /// Keys derived from environment variables are always lowercase while keys read from
+    /// a file are kept as written. A file loaded at the root containing `SERVER` would then
+    /// not merge with `server` coming from the environment. Use `KeyCase::Lower` to
+    /// reconcile both key spaces.
+    ///
+    /// Defaults to `KeyCase::AsIs`.
+    file_key_case: KeyCase,
+
+    /// Prefix removed from the top-level keys produced by parsing a secret file, as written in
+    /// the file, before `normalize_file_keys` applies.
+    ///
+    /// A file whose keys are `APP_SERVER` and `APP_REDIS` then matches a struct with `server`
+    /// and `redis` fields. Keys without the prefix, or made of the prefix only, are kept.
+    /// Defaults to none.
+    file_key_prefix: Option<String>,
+
+    /// Expand a leading `~` or `~user` of the secret path to the home directory.
+    ///
+    /// Only a tilde starting the path is expanded. Defaults to `false`.
+    #[cfg(feature = "tilde")]
+    expand_tilde: bool,
+
+    /// Text encoding of the secret file, transcoded to UTF-8 before parsing.
+    ///
+    /// Defaults to `Encoding::Utf8`.
+    #[cfg(feature = "encoding")]
+    encoding: Encoding,
+
+    /// Formats tried in order to parse secret files, the first one succeeding being used.
+    ///
+    /// When set, it replaces the inference of the format from the extension. Permissive
+    /// formats such as YAML accept most inputs and should come last. Defaults to empty.
+    format_chain: Vec<FileFormat>,
+
+    /// Format of the secret files, overriding the inference from the extension.
+    format: Option<FileFormat>,
+
+    /// Formats of the secret files loaded under given config keys, overriding the format
+    /// chain, `format` and the inference from the extension for these files only.
+    ///
+    /// Keys are the ones derived by the source, such as `db` for a file `db` of a directory.
+    key_formats: Map<String, FileFormat>,
+
+    /// Parsers of the secret files by extension, compared case-insensitively, taking
+    /// precedence over the formats of this crate and of `config`. Defaults to none.
+    formats: Map<String, Hook<dyn SecretFormat>>,
+
+    /// Handling of keys appearing more than once in a table of a secret file.
+    ///
+    /// Only some formats expose duplicates, see `DuplicatePolicy`. Defaults to
+    /// `DuplicatePolicy::Error`.
+    duplicate_keys: DuplicatePolicy,
+
+    /// Format used, with a warning, for files whose extension is not recognized instead of
+    /// failing. Defaults to failing.
+    fallback_format: Option<FileFormat>,
+
+    /// Reader of the secret files, the filesystem when unset.
+    ///
+    /// With a custom reader, files are always parsed by this crate, so their format must be
+    /// enabled on it.
+    reader: Option<Arc<dyn FileReader>>,
+
+    /// Read secret files as a raw string instead of parsing them.
+    ///
+    /// This suits secrets holding a single value, such as a password or a PEM key, whatever
+    /// their extension. Defaults to `false`.
+    raw: bool,
+
+    /// Newlines kept in raw secrets.
+    ///
+    /// Defaults to `NewlinePolicy::TrimTrailing`, which removes the final newline added by
+    /// most editors while keeping the lines of multi-line secrets.
+    newline: NewlinePolicy,
+
+    /// Replace the `${VAR}` placeholders of secret files with the values of the environment
+    /// before parsing them, instead of rendering them beforehand.
+    ///
+    /// Values are inserted as is, without escaping for the format of the file. Neither the
+    /// content nor the substituted values are logged. Defaults to `false`.
+    render_env: bool,
+
+    /// Handling of placeholders whose variable is not set when rendering secret files.
+    ///
+    /// Defaults to `UnresolvedPolicy::Error`.
+    unresolved: UnresolvedPolicy,
+
+    /// Provider of the variables substituted when rendering secret files and read by the
+    /// sources, such as the switch of `DirectorySecret::from_env_switch`. Set to the one of an
+    /// `EnvironmentSecretFile` given a provider, the process environment when unset.
+    vars: Option<Arc<dyn VarProvider>>,
+
+    /// Handling of the secret files that are zero bytes long. Defaults to
+    /// `EmptyFilePolicy::Load`.
+    empty_files: EmptyFilePolicy,
+
+    /// Identity file decrypting the secret files whose name ends with `.age`, the format of
+    /// their content being inferred from the rest of the name, such as `json` for
+    /// `db.json.age`.
+    ///
+    /// Encrypted files are always parsed by this crate, so their format must be enabled on
+    /// it. Defaults to none, `.age` files being read as is.
+    #[cfg(feature = "age")]
+    age_identity: Option<PathBuf>,
+
+    /// Read secret files as bytes, inserted as a base64 encoded string, for binary secrets
+    /// such as keystores or DER certificates.
+    ///
+    /// It takes precedence over the other ways of parsing the files. Defaults to `false`.
+    #[cfg(feature = "binary")]
+    binary: bool,
+
+    /// Decode the base64 content of secret files before parsing them, such as OAuth client
+    /// secrets delivered base64url encoded. Surrounding whitespace is ignored. Defaults to
+    /// `false`.
+    #[cfg(feature = "binary")]
+    base64: bool,
+
+    /// Alphabet and padding of the base64 content decoded with `base64(true)`. Defaults to
+    /// `Base64Variant::Standard`.
+    #[cfg(feature = "binary")]
+    base64_variant: crate::Base64Variant,
+
+    /// Load secret files with a `config::File` source, as plain config files are.
+    ///
+    /// The `File` is built with `required` from the source and `format` from these options,
+    /// then collected, so that extension resolution, missing files and parse errors behave
+    /// exactly like `config` does. The other reading options, such as the encoding, the
+    /// reader, the format chain, the fallback format, rendering, raw and binary secrets, are
+    /// ignored. `normalize_file_keys` still applies. Defaults to `false`.
+    file_source: bool,
+
+    /// Directory against which relative secret paths are resolved, instead of the current
+    /// directory. Defaults to none.
+    base_dir: Option<PathBuf>,
+
+    /// Origin recorded on loaded secrets.
+    ///
+    /// Defaults to `OriginMode::PathOnly`, so that the value of the variable, which can be
+    /// the secret itself, never shows in error messages.
+    origin_mode: OriginMode,
+
+    /// Maximum time spent reading each secret file, for mounts that may hang.
+    ///
+    /// Each file is read on a worker thread, a file not read in time failing with a
+    /// `TimedOut` read error, or being skipped when not required. A read blocked in
+    /// uninterruptible IO, as on a hard NFS mount, cannot be cancelled: its thread is left
+    /// behind until the read completes. As with a custom reader, the format of the files must
+    /// be enabled on this crate. Threads are not available on `wasm32-unknown-unknown`.
+    /// Defaults to none.
+    read_timeout: Option<Duration>,
+}
+
+impl Options {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn normalize_file_keys(mut self, case: KeyCase) -> Self {
+        self.file_key_case = case;
+        self
+    }
+
+    pub fn strip_file_key_prefix(mut self, prefix: &str) -> Self {
+        self.file_key_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Applies the options on file keys to the value parsed from the file at `uri`.
+    fn file_keys(&self, value: Value, uri: &str) -> Value {
+        let uri = uri.to_string();
+        let value = match (self.file_key_prefix.as_deref(), value.kind) {
+            (Some(prefix), ValueKind::Table(map)) => {
+                let map = map
+                    .into_iter()
+                    .map(|(key, value)| match key.strip_prefix(prefix) {
+                        Some(stripped) if !stripped.is_empty() => (stripped.to_string(), value),
+                        _ => (key, value),
+                    })
+                    .collect();
+                Value::new(Some(&uri), ValueKind::Table(map))
+            }
+            (_, kind) => Value::new(Some(&uri), kind),
+        };
+
+        self.file_key_case.apply_value(value, Some(&uri))
+    }
+
+    #[cfg(feature = "tilde")]
+    pub fn expand_tilde(mut self, expand: bool) -> Self {
+        self.expand_tilde = expand;
+        self
+    }
+
+    #[cfg(feature = "encoding")]
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn format_chain(mut self, formats: Vec<FileFormat>) -> Self {
+        self.format_chain = formats;
+        self
+    }
+
+    pub fn format(mut self, format: FileFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    pub fn key_format(mut self, key: &str, format: FileFormat) -> Self {
+        self.key_formats.insert(key.into(), format);
+        self
+    }
+
+    /// Returns the options reading the secret file loaded under `key`.
+    pub(crate) fn for_key(&self, key: &str) -> Cow<'_, Options> {
+        match self.key_formats.get(key) {
+            Some(format) => {
+                let mut options = self.clone();
+                options.format_chain.clear();
+                options.format = Some(*format);
+                Cow::Owned(options)
+            }
+            None => Cow::Borrowed(self),
+        }
+    }
+
+    /// Parses the secret files of extension `extension`, such as `kv` for `db.kv`, with
+    /// `format`, unless a format is set with `format(...)` or `format_chain(...)`.
+    ///
+    /// The format receives the bytes of the file after decryption and `base64(true)`, but
+    /// before any text handling: the `encoding`, the byte order mark and `render_env(true)`
+    /// are left to it. The keys it returns still go through `normalize_file_keys(...)`.
+    pub fn register_format(mut self, extension: &str, format: Box<dyn SecretFormat>) -> Self {
+        self.formats
+            .insert(extension.to_lowercase(), Hook::new(Arc::from(format)));
+        self
+    }
+
+    pub fn duplicate_keys(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    pub fn fallback_format(mut self, format: FileFormat) -> Self {
+        self.fallback_format = Some(format);
+        self
+    }
+
+    pub fn var_provider(mut self, provider: impl VarProvider + 'static) -> Self {
+        self.vars = Some(Arc::new(provider));
+        self
+    }
+
+    pub(crate) fn set_vars(&mut self, vars: Arc<dyn VarProvider>) {
+        self.vars = Some(vars);
+    }
+
+    /// Returns the value of the variable `name` from the provider of the options.
+    pub(crate) fn var(&self, name: &str) -> Option<String> {
+        match self.vars {
+            Some(ref vars) => vars.var(name),
+            None => env::var(name).ok(),
+        }
+    }
+
+    pub fn reader(mut self, reader: impl FileReader + 'static) -> Self {
+        self.reader = Some(Arc::new(reader));
+        self
+    }
+
+    pub fn raw(mut self, raw: bool) -> Self {
+        self.raw = raw;
+        self
+    }
+
+    pub fn newline(mut self, policy: NewlinePolicy) -> Self {
+        self.newline = policy;
+        self
+    }
+
+    pub fn render_env(mut self, render: bool) -> Self {
+        self.render_env = render;
+        self
+    }
+
+    pub fn unresolved_placeholders(mut self, policy: UnresolvedPolicy) -> Self {
+        self.unresolved = policy;
+        self
+    }
+
+    pub fn empty_files(mut self, policy: EmptyFilePolicy) -> Self {
+        self.empty_files = policy;
+        self
+    }
+
+    #[cfg(feature = "age")]
+    pub fn age_identity(mut self, path: impl Into<PathBuf>) -> Self {
+        self.age_identity = Some(path.into());
+        self
+    }
+
+    #[cfg(feature = "binary")]
+    pub fn binary(mut self, binary: bool) -> Self {
+        self.binary = binary;
+        self
+    }
+
+    #[cfg(feature = "binary")]
+    pub fn base64(mut self, base64: bool) -> Self {
+        self.base64 = base64;
+        self
+    }
+
+    #[cfg(feature = "binary")]
+    pub fn base64_variant(mut self, variant: crate::Base64Variant) -> Self {
+        self.base64_variant = variant;
+        self
+    }
+
+    pub fn file_source(mut self, file_source: bool) -> Self {
+        self.file_source = file_source;
+        self
+    }
+
+    pub fn base_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.base_dir = Some(dir.into());
+        self
+    }
+
+    pub fn origin_mode(mut self, mode: OriginMode) -> Self {
+        self.origin_mode = mode;
+        self
+    }
+
+    /// Labels the secret loaded under `key` from `path`, as given by `value`, with its origin.
+    pub(crate) fn label(&self, loaded: Value, key: &str, value: &str, path: &str) -> Value {
+        let origin = self.origin_mode.origin(key, value, path);
+        self.origin_mode.apply(loaded, origin)
+    }
+
+    /// Resolves relative secret paths against the directory of the config file at
+    /// `config_path`, keeping the paths it lists portable.
+    pub fn config_relative(self, config_path: impl AsRef<Path>) -> Self {
+        let dir = config_path
+            .as_ref()
+            .parent()
+            .unwrap_or_else(|| Path::new(""));
+        self.base_dir(dir)
+    }
+
+    /// Resolves relative secret paths against the directory of the current executable, for
+    /// secrets shipped beside the binary.
+    ///
+    /// Fails when the path of the executable is not available on the platform.
+    pub fn base_dir_exe(self) -> Result<Self, SecretError> {
+        let exe = env::current_exe().map_err(SecretError::CurrentExe)?;
+        match exe.parent() {
+            Some(dir) => Ok(self.base_dir(dir)),
+            None => Err(SecretError::CurrentExe(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} has no parent directory", exe.display()),
+            ))),
+        }
+    }
+
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Returns the reader of the secret files.
+    fn file_reader(&self) -> Arc<dyn FileReader> {
+        match self.reader {
+            Some(ref reader) => reader.clone(),
+            None => Arc::new(FsReader),
+        }
+    }
+
+    /// Returns the content of the secret file at `path`.
+    pub(crate) fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let timeout = match self.read_timeout {
+            Some(timeout) => timeout,
+            None => return self.file_reader().read(path),
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        let reader = self.file_reader();
+        let worker_path = path.to_path_buf();
+        thread::spawn(move || {
+            // The receiver is gone when the read took too long
+            let _ = sender.send(reader.read(&worker_path));
+        });
+
+        receiver.recv_timeout(timeout).unwrap_or_else(|_| {
+            Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("reading took more than {:?}", timeout),
+            ))
+        })
+    }
+
+    /// Whether the secret file at `path` exists.
+    pub(crate) fn exists(&self, path: &Path) -> bool {
+        match self.reader {
+            Some(ref reader) => reader.exists(path),
+            None => FsReader.exists(path),
+        }
+    }
+
+    /// Whether the secret file at `path`, read from the filesystem by `config::File`, exists
+    /// and is zero bytes long.
+    fn is_empty_file(&self, path: &Path) -> bool {
+        matches!(path.metadata(), Ok(metadata) if metadata.is_file() && metadata.len() == 0)
+    }
+
+    /// Whether the secret file at `path` is decrypted when read.
+    #[allow(unused_variables)]
+    fn decrypts(&self, path: &Path) -> bool {
+        #[cfg(feature = "age")]
+        if self.age_identity.is_some() && crate::decrypt::is_encrypted(path) {
+            return true;
+        }
+
+        false
+    }
+
+    /// Returns the path from which the format of the secret file at `path` is inferred, which
+    /// is the path of its decrypted content for encrypted files.
+    pub(crate) fn format_path<'a>(&self, path: &'a Path) -> Cow<'a, Path> {
+        #[cfg(feature = "age")]
+        if self.decrypts(path) {
+            return Cow::Owned(crate::decrypt::plain_path(path));
+        }
+
+        Cow::Borrowed(path)
+    }
+
+    /// Selects how the secret file at `path` is parsed.
+    fn parser(&self, path: &Path) -> Result<Parser, SecretError> {
+        let path = &*self.format_path(path);
+        if self.raw {
+            return Ok(Parser::Raw);
+        }
+        if !self.format_chain.is_empty() {
+            return Ok(Parser::Chain);
+        }
+        if let Some(format) = self.format {
+            return Ok(Parser::Format(Box::new(format), Some(format)));
+        }
+        if let Some(format) = self.registered_format(path) {
+            return Ok(Parser::Custom(format));
+        }
+        if let Some(format) = format::for_path(path) {
+            return Ok(Parser::Format(format, format::file_format(path)));
+        }
+
+        if !format::is_known(path) {
+            let extension = path
+                .extension()
+                .map(|extension| extension.to_string_lossy().into_owned());
+
+            return match self.fallback_format {
+                Some(format) => {
+                    log_warn!(
+                        "unknown extension {:?} for secret file {}, parsing it as {:?}",
+                        extension,
+                        path.display(),
+                        format
+                    );
+                    Ok(Parser::Format(Box::new(format), Some(format)))
+                }
+                None => Err(SecretError::UnknownFormat {
+                    path: path.to_path_buf(),
+                    extension,
+                }),
+            };
+        }
+
+        Ok(Parser::File)
+    }
+
+    /// Returns the format registered for the extension of `path`, if any.
+    fn registered_format(&self, path: &Path) -> Option<Hook<dyn SecretFormat>> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        self.formats.get(&extension).cloned()
+    }
+
+    /// Whether the content of the file at `path` must go through this crate before being
+    /// parsed.
+    fn needs_text(&self, path: &Path) -> bool {
+        if self.reader.is_some() || self.read_timeout.is_some() || self.render_env {
+            return true;
+        }
+
+        #[cfg(feature = "encoding")]
+        if self.encoding != Encoding::Utf8 {
+            return true;
+        }
+
+        #[cfg(feature = "binary")]
+        if self.base64 {
+            return true;
+        }
+
+        self.decrypts(path)
+    }
+
+    /// Reads the content of a secret file, decrypted if needed.
+    pub(crate) fn read_bytes(&self, path: &Path) -> Result<Vec<u8>, SecretError> {
+        let read = |path: &Path| {
+            self.read(path).map_err(|source| SecretError::Read {
+                path: path.to_path_buf(),
+                source,
+            })
+        };
+        let bytes = read(path)?;
+
+        #[cfg(feature = "age")]
+        if let Some(ref identity) = self.age_identity {
+            if crate::decrypt::is_encrypted(path) {
+                let identities = read(&self.resolve_path(identity))?;
+                return crate::decrypt::decrypt(path, &bytes, &identities);
+            }
+        }
+
+        Ok(bytes)
+    }
+
+    /// Parses `bytes`, the content of the secret file at `path` read from `uri`, the way
+    /// `load_value` parses the files it reads.
+    ///
+    /// The empty file policy, `binary(true)` and `base64(true)` apply to `bytes`, which are
+    /// then parsed with the format of `path`.
+    pub(crate) fn parse_bytes(
+        &self,
+        path: &Path,
+        uri: &str,
+        bytes: Vec<u8>,
+    ) -> Result<Value, SecretError> {
+        if self.empty_files == EmptyFilePolicy::Error && bytes.is_empty() {
+            return Err(SecretError::EmptyFile {
+                path: path.to_path_buf(),
+            });
+        }
+
+        #[cfg(feature = "binary")]
+        if self.binary {
+            use base64::Engine;
+
+            let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+            return Ok(Value::new(
+                Some(&uri.to_string()),
+                ValueKind::String(encoded),
+            ));
+        }
+
+        #[cfg(feature = "binary")]
+        let bytes = match self.base64 {
+            true => crate::encoded::decode_base64(path, &bytes, self.base64_variant)?,
+            false => bytes,
+        };
+
+        if let Parser::Custom(format) = self.parser(path)? {
+            return parse_custom(&*format, uri, &bytes, self);
+        }
+
+        let text = self.decode(path, bytes)?;
+        parse_value(path, uri, &text, self)
+    }
+
+    /// Whether the secret file at `path` is read and parsed by `config::File`.
+    fn parsed_by_config(&self, path: &Path) -> Result<bool, SecretError> {
+        #[cfg(feature = "binary")]
+        if self.binary {
+            return Ok(false);
+        }
+
+        Ok(matches!(self.parser(path)?, Parser::File) && !self.needs_text(path))
+    }
+
+    /// Decodes the content of the secret file at `path` to text.
+    pub(crate) fn decode(&self, path: &Path, bytes: Vec<u8>) -> Result<String, SecretError> {
+        #[cfg(feature = "encoding")]
+        let text = self.encoding.decode(&bytes).map(|text| text.into_owned());
+        #[cfg(not(feature = "encoding"))]
+        let text = String::from_utf8(bytes).ok().map(|mut text| {
+            // The byte order mark would otherwise prevent the format from recognizing the text
+            if text.starts_with(BOM) {
+                text.drain(..BOM.len_utf8());
+            }
+            text
+        });
+
+        let text = text.ok_or_else(|| SecretError::Decode {
+            path: path.to_path_buf(),
+        })?;
+        if !self.render_env {
+            return Ok(text);
+        }
+
+        match render::render(&text, |name| self.var(name), self.unresolved) {
+            Ok(rendered) => Ok(rendered.into_owned()),
+            Err(var) => Err(SecretError::UnresolvedPlaceholder {
+                path: path.to_path_buf(),
+                var,
+            }),
+        }
+    }
+
+    /// Resolves the path of a secret file before reading it.
+    pub(crate) fn resolve_path<'a>(&self, path: &'a Path) -> Cow<'a, Path> {
+        #[cfg(feature = "tilde")]
+        let path = if self.expand_tilde {
+            Cow::Owned(crate::tilde::expand(path))
+        } else {
+            Cow::Borrowed(path)
+        };
+        #[cfg(not(feature = "tilde"))]
+        let path = Cow::Borrowed(path);
+
+        match self.base_dir {
+            Some(ref dir) if path.is_relative() => Cow::Owned(dir.join(path)),
+            _ => path,
+        }
+    }
+}
+
+/// How the content of a secret file is parsed.
+enum Parser {
+    /// Parsed by `config::File`, inferring the format from the extension.
+    File,
+
+    /// Parsed by this crate with the given format, along with its `config` format if any.
+    Format(Box<dyn Format>, Option<FileFormat>),
+
+    /// Parsed by this crate with the first format of the format chain accepting it.
+    Chain,
+
+    /// Parsed from the bytes of the file with a registered format.
+    Custom(Hook<dyn SecretFormat>),
+
+    /// Not parsed, the content being a single string.
+    Raw,
+}
+
+/// Reads and parses the secret file at `path` into a config value.
+///
+/// The format is inferred from the extension of the file, unless set by the options, and the
+/// content is returned with the path of the file as origin. An unrecognized extension is an
+/// error unless a fallback format is set. Extensions of the formats enabled on this crate
+/// (such as `xml`) are parsed here, other ones are handed over to `config::File` unless the
+/// content needs to be decoded first.
+///
+/// The value is usually a table. When parsed by this crate, a JSON, JSON5 or YAML file whose
+/// root is an array or a scalar is returned as such, with the type given by the parser.
+pub fn load_value(path: &Path, options: &Options) -> Result<Value, SecretError> {
+    load_counted(path, options).map(|(value, _)| value)
+}
+
+/// Loads the secret file at `path` like `load_value`, along with the number of bytes of
+/// content parsed.
+///
+/// Files parsed by `config::File` are read by it, their size on disk is counted instead.
+pub(crate) fn load_counted(path: &Path, options: &Options) -> Result<(Value, u64), SecretError> {
+    let path = options.resolve_path(path);
+    let path = path.as_ref();
+    let uri = path.display().to_string();
+
+    if options.file_source || options.parsed_by_config(path)? {
+        if options.empty_files == EmptyFilePolicy::Error && options.is_empty_file(path) {
+            return Err(SecretError::EmptyFile {
+                path: path.to_path_buf(),
+            });
+        }
+
+        let value = if options.file_source {
+            load_file_source(path, options, true)?.unwrap_or_default()
+        } else {
+            let map = File::from(path).collect()?;
+            let value = Value::new(Some(&uri), ValueKind::Table(map));
+            options.file_keys(value, &uri)
+        };
+        return Ok((value, disk_size(path)));
+    }
+
+    let bytes = options.read_bytes(path)?;
+    let len = bytes.len() as u64;
+    Ok((options.parse_bytes(path, &uri, bytes)?, len))
+}
+
+/// Returns the size of the file at `path` on disk, for the files read by `config::File`.
+fn disk_size(path: &Path) -> u64 {
+    path.metadata().map(|metadata| metadata.len()).unwrap_or(0)
+}
+
+/// Loads the secret file at `path` like `load_counted`, returning `None` when the file does not
+/// exist and is not `required`.
+pub(crate) fn load_optional(
+    path: &Path,
+    options: &Options,
+    required: bool,
+) -> Result<Option<(Value, u64)>, SecretError> {
+    if options.file_source {
+        let path = options.resolve_path(path);
+        let value = load_file_source(&path, options, required)?;
+        return Ok(value.map(|value| (value, disk_size(&path))));
+    }
+
+    if !required && !options.exists(&options.resolve_path(path)) {
+        return Ok(None);
+    }
+
+    match load_counted(path, options) {
+        Err(SecretError::Read { source, .. })
+            if !required && source.kind() == io::ErrorKind::TimedOut =>
+        {
+            log_warn!("skipping secret file {}: {}", path.display(), source);
+            Ok(None)
+        }
+        result => result.map(Some),
+    }
+}
+
+/// Loads the secret file at `path` with a `config::File` source, returning `None` when the
+/// file is missing and not `required`.
+fn load_file_source(
+    path: &Path,
+    options: &Options,
+    required: bool,
+) -> Result<Option<Value>, SecretError> {
+    let uri = path.display().to_string();
+    let mut file = File::from(path).required(required);
+    if let Some(format) = options.format {
+        file = file.format(format);
+    }
+
+    let map = file.collect()?;
+    if map.is_empty() && !required && !path.exists() {
+        return Ok(None);
+    }
+
+    let value = Value::new(Some(&uri), ValueKind::Table(map));
+    Ok(Some(options.file_keys(value, &uri)))
+}
+
+/// Parses `text` as if it was the content of the secret file at `path`.
+///
+/// The format comes from `options` or from the extension of `path` and must be enabled on
+/// this crate.
+pub(crate) fn parse_value(
+    path: &Path,
+    uri: &str,
+    text: &str,
+    options: &Options,
+) -> Result<Value, SecretError> {
+    let value = match options.parser(path)? {
+        Parser::Format(format, kind) => {
+            parse_format(path, format.as_ref(), kind, uri, text, options)?
+        }
+        Parser::Chain => parse_chain(path, uri, text, options)?,
+        Parser::Custom(format) => return parse_custom(&*format, uri, text.as_bytes(), options),
+        Parser::Raw => {
+            let text = options.newline.apply(text).to_string();
+            return Ok(Value::new(Some(&uri.to_string()), ValueKind::String(text)));
+        }
+        Parser::File => {
+            return Err(SecretError::UnsupportedFormat {
+                path: path.to_path_buf(),
+            })
+        }
+    };
+
+    Ok(options.file_keys(value, uri))
+}
+
+/// Parses `bytes` read from `uri` with the registered `format`.
+fn parse_custom(
+    format: &dyn SecretFormat,
+    uri: &str,
+    bytes: &[u8],
+    options: &Options,
+) -> Result<Value, SecretError> {
+    let value = format.parse(bytes)?;
+    let value = Value::new(Some(&uri.to_string()), value.kind);
+    Ok(options.file_keys(value, uri))
+}
+
+/// Parses `text` with `format`, of the `config` format `kind`, enforcing the duplicate key
+/// policy of `options`.
+fn parse_format(
+    path: &Path,
+    format: &dyn Format,
+    kind: Option<FileFormat>,
+    uri: &str,
+    text: &str,
+    options: &Options,
+) -> Result<Value, SecretError> {
+    let text = duplicate::apply(kind, text, options.duplicate_keys).map_err(|key| {
+        SecretError::DuplicateKey {
+            path: path.to_path_buf(),
+            key,
+        }
+    })?;
+
+    Ok(format::parse(
+        &options.format_path(path),
+        format,
+        uri,
+        &text,
+    )?)
+}
+
+/// Parses `text` with the first format of the format chain of `options` that accepts it.
+///
+/// A duplicate key found by a format that can parse the text fails right away.
+fn parse_chain(
+    path: &Path,
+    uri: &str,
+    text: &str,
+    options: &Options,
+) -> Result<Value, SecretError> {
+    let mut attempts = Vec::new();
+
+    for format in &options.format_chain {
+        match parse_format(path, format, Some(*format), uri, text, options) {
+            Ok(value) => return Ok(value),
+            Err(err @ SecretError::DuplicateKey { .. }) => return Err(err),
+            Err(err) => attempts.push(format!("{:?}: {}", format, err)),
+        }
+    }
+
+    Err(SecretError::NoFormatMatched {
+        path: path.to_path_buf(),
+        attempts,
+    })
+}
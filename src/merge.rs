@@ -0,0 +1,53 @@
+use config::{Map, Value, ValueKind};
+
+/// Deep-merges `over` into `base`.
+///
+/// Tables are merged key by key, any other value of `over` replaces the one of `base`.
+pub(crate) fn merge_value(base: &mut Value, over: Value) {
+    match (&mut base.kind, over.kind) {
+        (ValueKind::Table(base), ValueKind::Table(over)) => merge_map(base, over),
+        (_, kind) => base.kind = kind,
+    }
+}
+
+/// Deep-merges the entries of `over` into `base`.
+pub(crate) fn merge_map(base: &mut Map<String, Value>, over: Map<String, Value>) {
+    for (key, value) in over {
+        match base.get_mut(&key) {
+            Some(existing) => merge_value(existing, value),
+            None => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Inserts every leaf of `value` in `flat` under `key` extended with the path of the leaf.
+///
+/// Table segments are joined with `delimiter`. Array elements get the `[index]` segments that
+/// `config` reads back as arrays when `arrays`, arrays being kept whole otherwise.
+pub(crate) fn flatten_value(
+    flat: &mut Map<String, Value>,
+    key: String,
+    value: Value,
+    delimiter: &str,
+    arrays: bool,
+) {
+    match value.kind {
+        ValueKind::Table(table) if !table.is_empty() => {
+            for (child, value) in table {
+                let child = format!("{}{}{}", key, delimiter, child);
+                flatten_value(flat, child, value, delimiter, arrays);
+            }
+        }
+        ValueKind::Array(array) if arrays && !array.is_empty() => {
+            for (index, value) in array.into_iter().enumerate() {
+                let child = format!("{}[{}]", key, index);
+                flatten_value(flat, child, value, delimiter, arrays);
+            }
+        }
+        _ => {
+            flat.insert(key, value);
+        }
+    }
+}
@@ -0,0 +1,30 @@
+/// Handling of the newlines of raw secrets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NewlinePolicy {
+    /// Keep the content exactly as written.
+    PreserveAll,
+
+    /// Remove the newlines ending the content, keeping the internal ones.
+    TrimTrailing,
+
+    /// Remove the whitespace, newlines included, surrounding the content.
+    TrimAll,
+}
+
+#[allow(clippy::derivable_impls)] // `#[default]` on variants requires Rust 1.62
+impl Default for NewlinePolicy {
+    fn default() -> Self {
+        NewlinePolicy::TrimTrailing
+    }
+}
+
+impl NewlinePolicy {
+    /// Applies this policy to the content of a raw secret.
+    pub fn apply<'a>(&self, text: &'a str) -> &'a str {
+        match self {
+            NewlinePolicy::PreserveAll => text,
+            NewlinePolicy::TrimTrailing => text.trim_end_matches(['\r', '\n']),
+            NewlinePolicy::TrimAll => text.trim(),
+        }
+    }
+}
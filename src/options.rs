@@ -0,0 +1,50 @@
+use serde::Deserialize;
+
+/// Description of an `EnvironmentSecretFile`, deserializable from a config file.
+///
+/// It lets a base config tell the application how to load its secrets, for example:
+///
+/// ```json
+/// { "prefix": "APP", "separator": "__", "required": false }
+/// ```
+///
+/// Unset fields keep the defaults of the source and unknown fields are rejected.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecretOptions {
+    /// Prefix of the variables to load, see `EnvironmentSecretFile::prefix`.
+    pub prefix: Option<String>,
+
+    /// Separator following the prefix, see `EnvironmentSecretFile::prefix_separator`.
+    pub prefix_separator: Option<String>,
+
+    /// Suffix of the variables to load, see `EnvironmentSecretFile::suffix`.
+    pub suffix: Option<String>,
+
+    /// Separator preceding the suffix, see `EnvironmentSecretFile::suffix_separator`.
+    pub suffix_separator: Option<String>,
+
+    /// Separator of nested keys, see `EnvironmentSecretFile::separator`.
+    pub separator: Option<String>,
+
+    /// Delimiter of the produced config keys, see `EnvironmentSecretFile::key_delimiter`.
+    pub key_delimiter: Option<String>,
+
+    /// Keep the prefix in the config keys, see `EnvironmentSecretFile::keep_prefix`.
+    pub keep_prefix: Option<bool>,
+
+    /// Key under which the secrets are nested, see `EnvironmentSecretFile::scope`.
+    pub scope: Option<String>,
+
+    /// Fail when a matched file is missing, see `EnvironmentSecretFile::required`.
+    pub required: Option<bool>,
+
+    /// Keys whose files may be missing, see `EnvironmentSecretFile::optional_keys`.
+    pub optional_keys: Vec<String>,
+
+    /// Keys that must be loaded, see `EnvironmentSecretFile::require_keys`.
+    pub require_keys: Vec<String>,
+
+    /// Variables to skip, see `EnvironmentSecretFile::ignore_vars`.
+    pub ignore_vars: Vec<String>,
+}
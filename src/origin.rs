@@ -0,0 +1,54 @@
+use config::{Value, ValueKind};
+
+/// Origin recorded on the values of secrets, shown by `config` in its error messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OriginMode {
+    /// Record no origin, on the secret and on every value nested in it.
+    None,
+
+    /// Record the path of the secret file only.
+    PathOnly,
+
+    /// Record the config key and the full value of the variable, as `secret:{key}:{value}`.
+    ///
+    /// The value of the variable may be the secret itself, for example with inline secrets.
+    Full,
+}
+
+#[allow(clippy::derivable_impls)] // `#[default]` on variants requires Rust 1.62
+impl Default for OriginMode {
+    fn default() -> Self {
+        OriginMode::PathOnly
+    }
+}
+
+impl OriginMode {
+    /// Returns the origin of the secret loaded under `key` from `path`, as given by `value`.
+    pub(crate) fn origin(&self, key: &str, value: &str, path: &str) -> Option<String> {
+        match self {
+            OriginMode::None => None,
+            OriginMode::PathOnly => Some(path.to_string()),
+            OriginMode::Full => Some(format!("secret:{}:{}", key, value)),
+        }
+    }
+
+    /// Sets the origin of a loaded secret, removing every nested origin in `OriginMode::None`.
+    pub(crate) fn apply(&self, value: Value, origin: Option<String>) -> Value {
+        match self {
+            OriginMode::None => strip(value),
+            _ => Value::new(origin.as_ref(), value.kind),
+        }
+    }
+}
+
+fn strip(value: Value) -> Value {
+    let kind = match value.kind {
+        ValueKind::Table(map) => {
+            ValueKind::Table(map.into_iter().map(|(k, v)| (k, strip(v))).collect())
+        }
+        ValueKind::Array(values) => ValueKind::Array(values.into_iter().map(strip).collect()),
+        kind => kind,
+    };
+
+    Value::new(None, kind)
+}
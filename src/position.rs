@@ -0,0 +1,16 @@
+/// Position of the marker, such as `FILE`, in the names of the variables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Position {
+    /// The marker ends the name, as in `APP_DB_PASSWORD_FILE`.
+    Suffix,
+
+    /// The marker starts the name, after the prefix if any, as in `APP_FILE_DB_PASSWORD`.
+    Prefix,
+}
+
+#[allow(clippy::derivable_impls)] // `#[default]` on variants requires Rust 1.62
+impl Default for Position {
+    fn default() -> Self {
+        Position::Suffix
+    }
+}
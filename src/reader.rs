@@ -0,0 +1,93 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Debug},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Reads the content of secret files.
+///
+/// The default reads from the filesystem. Another reader allows running where there is none,
+/// such as on `wasm32-unknown-unknown`.
+pub trait FileReader: Debug + Send + Sync {
+    /// Returns the content of the file at `path`.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Whether the file at `path` exists.
+    fn exists(&self, path: &Path) -> bool {
+        self.read(path).is_ok()
+    }
+}
+
+impl<T: FileReader + ?Sized> FileReader for Arc<T> {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        (**self).read(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        (**self).exists(path)
+    }
+}
+
+/// Reads files from the filesystem, which is the default reader.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FsReader;
+
+impl FileReader for FsReader {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+}
+
+/// Reads files held in memory, by exact path, whose `Debug` output lists the paths but never
+/// the contents.
+#[derive(Clone, Default)]
+pub struct MapReader(BTreeMap<PathBuf, Vec<u8>>);
+
+impl MapReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn file(mut self, path: impl Into<PathBuf>, content: impl Into<Vec<u8>>) -> Self {
+        self.0.insert(path.into(), content.into());
+        self
+    }
+}
+
+impl<P: Into<PathBuf>, C: Into<Vec<u8>>> FromIterator<(P, C)> for MapReader {
+    fn from_iter<I: IntoIterator<Item = (P, C)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(path, content)| (path.into(), content.into()))
+                .collect(),
+        )
+    }
+}
+
+impl Debug for MapReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MapReader ")?;
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+impl FileReader for MapReader {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.0.get(path).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no file {} in memory", path.display()),
+            )
+        })
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.0.contains_key(path)
+    }
+}
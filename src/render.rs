@@ -0,0 +1,54 @@
+use std::borrow::Cow;
+
+/// Handling of the `${VAR}` placeholders of a rendered secret file whose variable is not set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnresolvedPolicy {
+    /// Fail to load the secret file.
+    Error,
+
+    /// Keep the placeholder as written.
+    Keep,
+}
+
+#[allow(clippy::derivable_impls)] // `#[default]` on variants requires Rust 1.62
+impl Default for UnresolvedPolicy {
+    fn default() -> Self {
+        UnresolvedPolicy::Error
+    }
+}
+
+/// Replaces the `${VAR}` placeholders of `text` with the values given by `lookup`.
+///
+/// An unterminated `${` is kept as written. Fails with the name of the first variable not
+/// found when unresolved placeholders are an error.
+pub(crate) fn render<'a>(
+    text: &'a str,
+    lookup: impl Fn(&str) -> Option<String>,
+    policy: UnresolvedPolicy,
+) -> Result<Cow<'a, str>, String> {
+    if !text.contains("${") {
+        return Ok(Cow::Borrowed(text));
+    }
+
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("${") {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+
+        rendered.push_str(&rest[..start]);
+        let name = &rest[start + 2..end];
+        match lookup(name) {
+            Some(value) => rendered.push_str(&value),
+            None if policy == UnresolvedPolicy::Keep => rendered.push_str(&rest[start..=end]),
+            None => return Err(name.to_string()),
+        }
+        rest = &rest[end + 1..];
+    }
+
+    rendered.push_str(rest);
+    Ok(Cow::Owned(rendered))
+}
@@ -1,6 +1,36 @@
-use std::{env, path::Path};
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    env,
+    fmt::{self, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
 
-use config::{ConfigError, File, Map, Source, Value, ValueKind};
+use config::{Config, ConfigError, Map, Source, Value, ValueKind};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    audit::AuditReport,
+    cache::Cache,
+    hook::Hook,
+    load::{load_optional, parse_value, BOM},
+    merge::{flatten_value, merge_map, merge_value},
+    stats::{ProvenanceCell, StatsCell},
+    vars::EnvVarProvider,
+    CollectStats, KeyCase, Options, Position, SecretError, SecretOptions, SuffixRule, VarProvider,
+};
+
+type PostProcess = dyn Fn(&mut Map<String, Value>) + Send + Sync;
+type VarFilter = dyn Fn(&str) -> bool + Send + Sync;
+type ValueTransform = dyn Fn(Value) -> Result<Value, SecretError> + Send + Sync;
+type Resolver = dyn Fn(&str) -> Result<Option<Value>, SecretError> + Send + Sync;
+
+/// Interval at which `wait_for(...)` checks whether the secret files appeared.
+const WAIT_INTERVAL: Duration = Duration::from_millis(100);
 
 #[derive(Clone, Debug, Default)]
 pub struct EnvironmentSecretFile {
@@ -15,6 +45,9 @@ pub struct EnvironmentSecretFile {
 
     /// Optional character sequence that separates the prefix from the rest of the key
     /// Defaults to `separator` or `_`
+    ///
+    /// An empty separator glues the prefix to the key, `MYAPPDBHOST_FILE` then giving `dbhost`.
+    /// The whole file is then read from `MYAPP_FILE`, with the suffix separator.
     prefix_separator: Option<String>,
 
     /// Suffix that will limit secrets in the environment to only keys that ends with the defined
@@ -23,12 +56,25 @@ pub struct EnvironmentSecretFile {
     /// A suffix with a separator of `_` is tested to be present on each key before its considered
     /// to be part of the secret environment.
     ///
-    /// The default value is `FILE`.
+    /// The default value is `FILE`. An empty suffix disables suffix matching, which requires a
+    /// `prefix` or an explicit `match_all(true)`.
     ///
     /// For example, the key `CONFIG_FILE` would parse the file pointed in the variable and collect
     /// the content config into the key `config`.
     suffix: Option<String>,
 
+    /// Position of the suffix in the names of the variables, `Position::Prefix` matching
+    /// marker-first names such as `FILE_DB_PASSWORD`.
+    ///
+    /// The suffix separator then follows the marker instead of preceding it. Defaults to
+    /// `Position::Suffix`.
+    marker_position: Position,
+
+    /// Variables that must carry the suffix, `SuffixRule::WithoutPrefix` making it optional
+    /// for the variables with the prefix and matching the variables without the prefix
+    /// that have it. Defaults to `SuffixRule::Always`.
+    suffix_rule: SuffixRule,
+
     /// Optional character sequence that separates the prefix from the rest of the key
     /// Defaults to `separator` or `_`
     suffix_separator: Option<String>,
@@ -38,8 +84,202 @@ pub struct EnvironmentSecretFile {
     /// an environment key of `REDIS_PASSWORD` to match.
     separator: Option<String>,
 
+    /// Other character sequences read as `separator` in the names of the variables, so that
+    /// `MYAPP-DB-HOST-FILE` matches like `MYAPP_DB_HOST_FILE` with `-`. The prefix and suffix
+    /// separators must follow `separator` for them to be replaced too. Defaults to none.
+    alternate_separators: Vec<String>,
+
+    /// Optional character sequence replacing `separator` in the derived config keys.
+    /// Defaults to `.`
+    ///
+    /// `config` always splits keys on `.` to nest them, so the default is the one to use when
+    /// the source is added to a `config::Config`. Another delimiter produces flat keys, which
+    /// can be useful when the collected map is consumed directly.
+    key_delimiter: Option<String>,
+
     // Preserve the prefix while parsing
     keep_prefix: bool,
+
+    /// Keep the original case of the prefix when `keep_prefix` is set, instead of lowercasing
+    /// it like the rest of the key. Defaults to `false`.
+    keep_prefix_case: bool,
+
+    /// Key prepended to every key derived from a variable name, such as `services.auth`.
+    ///
+    /// It is joined to the derived key with the key delimiter. Files loaded at the root are
+    /// not affected. Defaults to none.
+    scope: Option<String>,
+
+    /// Case applied to each segment of the keys derived from variable names.
+    ///
+    /// Derived keys are lowercase, such as `db_host`. With the `case` feature, they can be
+    /// converted to the naming of the config, such as `db-host` with `KeyCase::Kebab`.
+    /// Defaults to `KeyCase::AsIs`.
+    key_case: KeyCase,
+
+    /// Merge the inline variable over the content of the secret file.
+    ///
+    /// When both `DB_FILE` and `DB` are set, `DB` is parsed with the format of the file
+    /// pointed by `DB_FILE` and deep-merged over it, values of `DB` taking precedence.
+    /// Defaults to `false`.
+    merge_inline: bool,
+
+    /// Skip matched variables pointing at files that do not exist instead of failing.
+    /// Set with `required(false)`, defaults to `false`.
+    optional: bool,
+
+    /// Derived config keys whose missing files are skipped even when `required(true)`.
+    optional_keys: HashSet<String>,
+
+    /// Treat the value of a variable as a list of paths separated like the `PATH` variable of
+    /// the platform (`:` on Unix, `;` on Windows).
+    ///
+    /// Files are read in order and deep-merged, later files overriding earlier ones. Missing
+    /// files follow `required(...)`. Defaults to `false`.
+    path_list: bool,
+
+    /// Config keys that `collect()` must produce, failing with the variables to set otherwise.
+    ///
+    /// A key is found when inserted as is or nested in a loaded table. Defaults to empty.
+    require_keys: Vec<String>,
+
+    /// Fail when `collect()` produces no secret, which usually means that the prefix or the
+    /// suffix does not match the environment. Defaults to `false`.
+    require_nonempty: bool,
+
+    /// Names of variables never read, even when they match.
+    ///
+    /// Names are compared case-insensitively, like the prefix and the suffix.
+    ignore_vars: HashSet<String>,
+
+    /// Config keys already provided by other sources, which this source never emits.
+    ///
+    /// Keys are compared to the keys this source would insert, either derived from a variable
+    /// name or read from a file loaded at the root. Matching variables are skipped before
+    /// their file is read.
+    seed_keys: HashSet<String>,
+
+    /// Secrets provided in process, such as by a test, inserted under their key without
+    /// reading the filesystem. Defaults to empty.
+    ///
+    /// They are deep-merged with the secrets loaded from files, which take precedence unless
+    /// `inline_secrets_override(true)`.
+    inline_secrets: InlineSecrets,
+
+    /// Let the inline secrets take precedence over the secrets loaded from files. Defaults to
+    /// `false`.
+    inline_secrets_override: bool,
+
+    /// Optional marker indicating that the value of a variable is the content of the secret
+    /// instead of the path of a file.
+    ///
+    /// For example with a marker of `inline:`, `DB_FILE=inline:{"port":5000}` is parsed as is.
+    /// Since there is no file extension, the format must be set in the options with
+    /// `format(...)` or `format_chain(...)`. Defaults to none.
+    content_prefix: Option<String>,
+
+    /// Optional marker indicating that the value of a variable is the name of another variable
+    /// holding the path of the secret file.
+    ///
+    /// For example with a marker of `__ref:`, `DB_FILE=__ref:DB_FILE_REAL` reads the file
+    /// pointed by `DB_FILE_REAL`. References are followed until a value without the marker is
+    /// found, a chain coming back to a variable already visited being an error.
+    indirection_prefix: Option<String>,
+
+    /// Acknowledge that, without prefix and with suffix matching disabled, every non-empty
+    /// environment variable is read as the path of a secret file. Defaults to `false`.
+    match_all: bool,
+
+    /// Treat environment variables containing only whitespace as unset, like empty ones.
+    /// Defaults to `false`.
+    trim_value_check: bool,
+
+    /// Variable and value gating the source: unless the variable is set to exactly this
+    /// value, such as `FEATURE_X_ENABLED=true`, no secret is loaded. Defaults to none.
+    enable_if: Option<(String, String)>,
+
+    /// Insert every leaf of the secrets under its full key, such as `server.port`, instead of
+    /// inserting whole tables. Defaults to `false`.
+    flatten: bool,
+
+    /// Flatten arrays too when `flatten`, elements being inserted under `config` index keys
+    /// such as `redis.nodes[0]`, which deserialize back into a `Vec`. Segments like
+    /// `redis.nodes.0` would be read as table keys instead. Defaults to `false`, inserting
+    /// arrays whole.
+    flatten_arrays: bool,
+
+    /// Also insert each key derived from a variable name under its flat form, joined with
+    /// `_`, so that `server.host` is readable as `server_host` too while code migrates from
+    /// one to the other. Keys loaded from a whole file at the root are not duplicated.
+    /// Defaults to `false`.
+    dual_keys: bool,
+
+    /// Field of the secret files loaded at the root whose value becomes their key.
+    ///
+    /// For example with `name`, a file `{"name": "db-primary", "port": 5432}` is inserted
+    /// whole under `db-primary` instead of having its entries inserted at the root. A file
+    /// without this field, or whose field is not a scalar, is an error. Defaults to none.
+    key_field: Option<String>,
+
+    /// Variable holding a JSON object mapping config keys to the paths of their secret files,
+    /// such as `{"db.password": "/run/secrets/dbpw"}`, read instead of matching variables.
+    /// Defaults to none.
+    ///
+    /// Missing files follow `required(...)` and `optional_keys(...)`. An unset or empty
+    /// variable loads no secret.
+    #[cfg(feature = "json")]
+    manifest_var: Option<String>,
+
+    /// Grace period during which `collect()` waits for the secret files to appear, such as a
+    /// secret volume mounted a moment after the process starts. Defaults to none.
+    ///
+    /// While a required file is missing, or matched variables point at files and none of them
+    /// exists, the files are checked again every 100 milliseconds until they appear or the
+    /// period ends. Secrets are then loaded as usual: with `required(true)`, files still
+    /// missing fail the collection, with `required(false)` they are skipped.
+    wait_for: Option<Duration>,
+
+    /// Options controlling how matched secret files are read.
+    options: Options,
+
+    /// Optional callback rewriting the collected map before it is returned.
+    ///
+    /// It runs once at the end of `collect()`, after all files have been read and merged.
+    post_process: Option<Hook<PostProcess>>,
+
+    /// Callbacks rewriting the value inserted under a config key, such as splitting a
+    /// connection string into `host`, `port` and `database` subkeys.
+    ///
+    /// They run after the secret file is parsed and before its value enters the map.
+    value_transforms: Map<String, Hook<ValueTransform>>,
+
+    /// Optional predicate called with the name of each environment variable.
+    ///
+    /// Variables for which it returns `false` are skipped before any prefix or suffix matching.
+    var_filter: Option<Hook<VarFilter>>,
+
+    /// Optional callback called with each derived config key, supplying its value from another
+    /// backend, such as an internal secret library, instead of the secret file.
+    ///
+    /// When it returns `None`, the secret file of the variable is loaded as usual. Values it
+    /// supplies still go through `value_transform(...)` but have no provenance.
+    resolver: Option<Hook<Resolver>>,
+
+    /// Memoize the result of the first successful `collect()` and return it on subsequent
+    /// calls until `invalidate()` is called. The cache is shared by the clones of the source.
+    /// Defaults to `false`.
+    cache: bool,
+
+    cached: Cache,
+
+    /// Provider of the variables to match, the process environment when unset.
+    vars: Option<Arc<dyn VarProvider>>,
+
+    /// Statistics of the last successful `collect()` that read the environment.
+    stats: StatsCell,
+
+    /// Paths of the files that provided each key during the last successful `collect()`.
+    provenance: ProvenanceCell,
 }
 
 impl EnvironmentSecretFile {
@@ -50,28 +290,139 @@ impl EnvironmentSecretFile {
         }
     }
 
+    pub fn with_suffix(s: &str) -> Self {
+        Self {
+            suffix: Some(s.into()),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_prefix_and_suffix(prefix: &str, suffix: &str) -> Self {
+        Self {
+            prefix: Some(prefix.into()),
+            suffix: Some(suffix.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Preset for the `_FILE` convention of Docker images, such as `APP_DB_PASSWORD_FILE`
+    /// inserted under `db.password` with a prefix of `APP`.
+    ///
+    /// The suffix is `FILE` and `_` both separates the markers and nests the keys.
+    pub fn docker(prefix: &str) -> Self {
+        Self::with_prefix_and_suffix(prefix, "FILE").separator("_")
+    }
+
+    /// Builds a source from its deserialized description, unset options keeping their defaults.
+    pub fn from_options(options: SecretOptions) -> Self {
+        let mut source = Self {
+            prefix: options.prefix,
+            prefix_separator: options.prefix_separator,
+            suffix: options.suffix,
+            suffix_separator: options.suffix_separator,
+            separator: options.separator,
+            key_delimiter: options.key_delimiter,
+            scope: options.scope,
+            ..Self::default()
+        };
+
+        if let Some(keep) = options.keep_prefix {
+            source = source.keep_prefix(keep);
+        }
+        if let Some(required) = options.required {
+            source = source.required(required);
+        }
+
+        source
+            .optional_keys(options.optional_keys.iter().map(String::as_str))
+            .require_keys(options.require_keys.iter().map(String::as_str))
+            .ignore_vars(options.ignore_vars.iter().map(String::as_str))
+    }
+
     pub fn prefix(mut self, s: &str) -> Self {
+        self.set_prefix(s);
+        self
+    }
+
+    pub fn set_prefix(&mut self, s: &str) -> &mut Self {
         self.prefix = Some(s.into());
         self
     }
 
     pub fn prefix_separator(mut self, s: &str) -> Self {
+        self.set_prefix_separator(s);
+        self
+    }
+
+    pub fn set_prefix_separator(&mut self, s: &str) -> &mut Self {
         self.prefix_separator = Some(s.into());
         self
     }
 
     pub fn suffix(mut self, s: &str) -> Self {
+        self.set_suffix(s);
+        self
+    }
+
+    pub fn set_suffix(&mut self, s: &str) -> &mut Self {
         self.suffix = Some(s.into());
         self
     }
 
+    pub fn marker_position(mut self, position: Position) -> Self {
+        self.marker_position = position;
+        self
+    }
+
+    pub fn suffix_required_when(mut self, rule: SuffixRule) -> Self {
+        self.suffix_rule = rule;
+        self
+    }
+
     pub fn suffix_separator(mut self, s: &str) -> Self {
+        self.set_suffix_separator(s);
+        self
+    }
+
+    pub fn set_suffix_separator(&mut self, s: &str) -> &mut Self {
         self.suffix_separator = Some(s.into());
         self
     }
 
     pub fn separator(mut self, s: &str) -> Self {
+        self.set_separator(s);
+        self
+    }
+
+    pub fn set_separator(&mut self, s: &str) -> &mut Self {
+        self.separator = Some(s.into());
+        self
+    }
+
+    /// Accepts any of `separators` between the segments of the variable names, the first one
+    /// becoming `separator` and the others being read as it.
+    pub fn separators<'a>(mut self, separators: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut separators = separators.into_iter().map(String::from);
+        self.separator = separators.next();
+        self.alternate_separators = separators.collect();
+        self
+    }
+
+    /// Sets `separator`, `prefix_separator` and `suffix_separator` to the same value.
+    pub fn uniform_separator(mut self, s: &str) -> Self {
         self.separator = Some(s.into());
+        self.prefix_separator = Some(s.into());
+        self.suffix_separator = Some(s.into());
+        self
+    }
+
+    pub fn key_delimiter(mut self, s: &str) -> Self {
+        self.set_key_delimiter(s);
+        self
+    }
+
+    pub fn set_key_delimiter(&mut self, s: &str) -> &mut Self {
+        self.key_delimiter = Some(s.into());
         self
     }
 
@@ -79,126 +430,1384 @@ impl EnvironmentSecretFile {
         self.keep_prefix = keep;
         self
     }
-}
 
-impl Source for EnvironmentSecretFile {
-    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
-        Box::new((*self).clone())
+    pub fn keep_prefix_case(mut self, keep: bool) -> Self {
+        self.keep_prefix_case = keep;
+        self
     }
 
-    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
-        let mut m = Map::new();
-
-        let separator = self.separator.as_deref().unwrap_or("");
-        let prefix_separator = match (self.prefix_separator.as_deref(), self.separator.as_deref()) {
-            (Some(pre), _) => pre,
-            (None, Some(sep)) => sep,
-            (None, None) => "_",
-        };
-        let suffix_separator = match (self.suffix_separator.as_deref(), self.separator.as_deref()) {
-            (Some(suf), _) => suf,
-            (None, Some(sep)) => sep,
-            (None, None) => "_",
-        };
+    pub fn scope(mut self, scope: &str) -> Self {
+        self.set_scope(scope);
+        self
+    }
 
-        let prefix_pattern = self
-            .prefix
-            .as_ref()
-            .map(|prefix| format!("{}{}", prefix, prefix_separator).to_lowercase());
+    pub fn set_scope(&mut self, scope: &str) -> &mut Self {
+        self.scope = Some(scope.into());
+        self
+    }
 
-        let suffix = self.suffix.as_ref().map_or_else(|| "FILE", |s| s.as_str());
-        let suffix_pattern = format!("{}{}", suffix_separator, suffix).to_lowercase();
+    pub fn key_case(mut self, case: KeyCase) -> Self {
+        self.key_case = case;
+        self
+    }
 
-        let full_pattern = if let Some(prefix) = self.prefix.as_ref() {
-            if prefix_separator == suffix_separator {
-                format!("{}{}{}", prefix, prefix_separator, suffix).to_lowercase()
-            } else {
-                format!("{}{}", prefix, suffix)
-            }
+    /// Converts the derived keys to uppercase, `db_host` becoming `DB_HOST`, as a shorthand
+    /// for `key_case(KeyCase::Upper)`. Disabling it keeps the keys lowercase.
+    pub fn upcase_keys(self, upcase: bool) -> Self {
+        self.key_case(if upcase {
+            KeyCase::Upper
         } else {
-            suffix.to_string()
-        };
+            KeyCase::AsIs
+        })
+    }
 
-        let mut error: Option<ConfigError> = None;
+    pub fn merge_inline(mut self, merge: bool) -> Self {
+        self.merge_inline = merge;
+        self
+    }
 
-        env::vars().for_each(|(key, value): (String, String)| {
-            // Stop processing on error
-            if error.as_ref().is_some() {
-                return;
-            }
+    pub fn required(mut self, required: bool) -> Self {
+        self.set_required(required);
+        self
+    }
 
-            // Treat empty environment variables as unset
-            if value.is_empty() {
-                return;
-            }
+    pub fn set_required(&mut self, required: bool) -> &mut Self {
+        self.optional = !required;
+        self
+    }
 
-            let mut key = key.to_lowercase();
+    pub fn optional_keys<'a>(mut self, keys: impl IntoIterator<Item = &'a str>) -> Self {
+        self.optional_keys
+            .extend(keys.into_iter().map(String::from));
+        self
+    }
 
-            if key == full_pattern {
-                let path = Path::new(&value);
-                let file = File::from(path);
-                let map = file.collect();
+    pub fn path_list(mut self, path_list: bool) -> Self {
+        self.path_list = path_list;
+        self
+    }
 
-                match map {
-                    Ok(map) => {
-                        for (key, value) in map.into_iter() {
-                            m.insert(key, value);
-                        }
-                    }
-                    Err(err) => {
-                        error = Some(err);
-                    }
-                }
+    pub fn require_keys<'a>(mut self, keys: impl IntoIterator<Item = &'a str>) -> Self {
+        self.require_keys.extend(keys.into_iter().map(String::from));
+        self
+    }
 
-                return;
-            }
+    pub fn require_nonempty(mut self, require: bool) -> Self {
+        self.require_nonempty = require;
+        self
+    }
 
-            // Check for prefix
-            if let Some(ref prefix_pattern) = prefix_pattern {
-                if key.starts_with(prefix_pattern) {
-                    if !self.keep_prefix {
-                        // Remove this prefix from the key
-                        key = key[prefix_pattern.len()..].to_string();
-                    }
-                } else {
-                    // Skip this key
-                    return;
-                }
-            }
+    pub fn ignore_vars<'a>(mut self, names: impl IntoIterator<Item = &'a str>) -> Self {
+        self.ignore_vars
+            .extend(names.into_iter().map(str::to_lowercase));
+        self
+    }
 
-            // Check for suffix
-            if key.ends_with(&suffix_pattern) {
-                // Remove this suffix from the key
-                let len = key.len() - suffix_pattern.len();
-                key = key[..len].to_string();
-            } else {
-                // Skip this key
-                return;
-            }
+    pub fn seed_keys(mut self, keys: impl IntoIterator<Item = String>) -> Self {
+        self.seed_keys.extend(keys);
+        self
+    }
 
-            // If separator is given replace with `.`
-            if !separator.is_empty() {
-                key = key.replace(separator, ".");
-            }
+    pub fn with_inline_secrets(
+        mut self,
+        secrets: impl IntoIterator<Item = (String, Value)>,
+    ) -> Self {
+        self.inline_secrets.0.extend(secrets);
+        self
+    }
 
-            let path = Path::new(&value);
-            let file = File::from(path);
-            let map = file.collect();
+    pub fn inline_secrets_override(mut self, prevail: bool) -> Self {
+        self.inline_secrets_override = prevail;
+        self
+    }
 
-            match map {
-                Ok(map) => {
-                    let uri = format!("secret:{}:{}", key, value);
-                    m.insert(key, Value::new(Some(&uri), ValueKind::Table(map)));
-                }
-                Err(err) => {
-                    error = Some(err);
+    pub fn content_prefix(mut self, s: &str) -> Self {
+        self.content_prefix = Some(s.into());
+        self
+    }
+
+    pub fn indirection_prefix(mut self, s: &str) -> Self {
+        self.indirection_prefix = Some(s.into());
+        self
+    }
+
+    pub fn match_all(mut self, match_all: bool) -> Self {
+        self.match_all = match_all;
+        self
+    }
+
+    pub fn trim_value_check(mut self, trim: bool) -> Self {
+        self.trim_value_check = trim;
+        self
+    }
+
+    pub fn enable_if_var(mut self, name: &str, expected: &str) -> Self {
+        self.enable_if = Some((name.into(), expected.into()));
+        self
+    }
+
+    pub fn flatten(mut self, flatten: bool) -> Self {
+        self.flatten = flatten;
+        self
+    }
+
+    pub fn flatten_arrays(mut self, flatten: bool) -> Self {
+        self.flatten_arrays = flatten;
+        self
+    }
+
+    pub fn dual_keys(mut self, dual: bool) -> Self {
+        self.dual_keys = dual;
+        self
+    }
+
+    pub fn key_from_field(mut self, field: &str) -> Self {
+        self.key_field = Some(field.into());
+        self
+    }
+
+    #[cfg(feature = "json")]
+    pub fn manifest_var(mut self, name: &str) -> Self {
+        self.manifest_var = Some(name.into());
+        self
+    }
+
+    /// Returns the keys and paths listed by the manifest variable, if set.
+    #[cfg(feature = "json")]
+    fn manifest(&self) -> Result<Vec<(String, String)>, SecretError> {
+        let name = match self.manifest_var {
+            Some(ref name) => name,
+            None => return Ok(Vec::new()),
+        };
+        let value = match self.vars().var(name) {
+            Some(value) if !self.is_unset(&value) => value,
+            _ => return Ok(Vec::new()),
+        };
+
+        let entries: std::collections::BTreeMap<String, String> = serde_json::from_str(&value)
+            .map_err(|err| {
+                // The message of `serde_json` may quote the content, only its position is kept
+                let kind = match err.classify() {
+                    serde_json::error::Category::Syntax => "invalid JSON",
+                    serde_json::error::Category::Eof => "truncated JSON",
+                    _ => "expected a JSON object of string paths",
+                };
+                SecretError::Manifest {
+                    var: name.clone(),
+                    reason: format!("{} at line {} column {}", kind, err.line(), err.column()),
                 }
-            }
-        });
+            })?;
+        Ok(entries.into_iter().collect())
+    }
+
+    /// Returns the variables to match, none when secrets are listed by a manifest.
+    fn matched_vars(&self) -> Vec<(String, String)> {
+        #[cfg(feature = "json")]
+        if self.manifest_var.is_some() {
+            return Vec::new();
+        }
+
+        self.vars().vars()
+    }
+
+    /// Returns the entries of the manifest then the matched variables, with their value and
+    /// how they match, for the diagnostics that read no file.
+    ///
+    /// Variables matching the patterns but rejected by the filters are `Matched::Rejected`.
+    fn candidates(
+        &self,
+        patterns: &Patterns,
+    ) -> Result<Vec<(String, String, Matched)>, SecretError> {
+        let mut candidates = Vec::new();
+
+        #[cfg(feature = "json")]
+        for (key, path) in self.manifest()? {
+            let name = self.manifest_var.clone().unwrap_or_default();
+            candidates.push((name, path, Matched::Listed(key)));
+        }
+
+        for (name, value) in self.matched_vars() {
+            let matched = match self.match_name(patterns, &name) {
+                Matched::None => Matched::None,
+                _ if !self.accepts(&name) => Matched::Rejected,
+                matched => matched,
+            };
+            candidates.push((name, value, matched));
+        }
+
+        Ok(candidates)
+    }
+
+    pub fn wait_for(mut self, period: Duration) -> Self {
+        self.wait_for = Some(period);
+        self
+    }
+
+    pub fn options(mut self, options: Options) -> Self {
+        self.set_options(options);
+        self
+    }
+
+    pub fn set_options(&mut self, options: Options) -> &mut Self {
+        self.options = options;
+        if let Some(ref vars) = self.vars {
+            self.options.set_vars(vars.clone());
+        }
+        self
+    }
 
-        match error {
-            Some(err) => Err(err),
-            None => Ok(m),
+    pub fn post_process(
+        mut self,
+        f: impl Fn(&mut Map<String, Value>) + Send + Sync + 'static,
+    ) -> Self {
+        self.post_process = Some(Hook::new(Arc::new(f)));
+        self
+    }
+
+    pub fn value_transform(
+        mut self,
+        key: &str,
+        f: impl Fn(Value) -> Result<Value, SecretError> + Send + Sync + 'static,
+    ) -> Self {
+        self.value_transforms
+            .insert(key.into(), Hook::new(Arc::new(f)));
+        self
+    }
+
+    /// Reads the variables from `provider` instead of the process environment, including the
+    /// ones substituted by `render_env(true)`.
+    pub fn var_provider(mut self, provider: impl VarProvider + 'static) -> Self {
+        let vars: Arc<dyn VarProvider> = Arc::new(provider);
+        self.options.set_vars(vars.clone());
+        self.vars = Some(vars);
+        self
+    }
+
+    /// Returns the provider of the variables to match.
+    fn vars(&self) -> &dyn VarProvider {
+        match self.vars {
+            Some(ref vars) => vars.as_ref(),
+            None => &EnvVarProvider,
+        }
+    }
+
+    pub fn cache(mut self, cache: bool) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Drops the cached result so that the next `collect()` reads the secrets again.
+    pub fn invalidate(&self) {
+        self.cached.invalidate();
+    }
+
+    /// Returns the path of the secret file pointed by the variable `name` of value `value`.
+    fn resolve_path(&self, name: &str, value: String) -> Result<String, SecretError> {
+        let marker = match self.indirection_prefix.as_deref() {
+            Some(marker) => marker,
+            None => return Ok(value),
+        };
+
+        let mut chain = vec![name.to_string()];
+        let mut value = value;
+
+        while let Some(target) = value.strip_prefix(marker) {
+            let target = target.to_string();
+            if chain.contains(&target) {
+                chain.push(target);
+                return Err(SecretError::ReferenceCycle { chain });
+            }
+
+            value = self
+                .vars()
+                .var(&target)
+                .ok_or_else(|| SecretError::UnresolvedReference {
+                    var: chain[chain.len() - 1].clone(),
+                    target: target.clone(),
+                })?;
+            chain.push(target);
+        }
+
+        Ok(value)
+    }
+
+    /// Returns the number of files read and their total size during the last successful
+    /// `collect()` that read the environment, a cached result not counting as a run.
+    pub fn last_stats(&self) -> CollectStats {
+        self.stats.get()
+    }
+
+    /// Returns the path of the secret file that provided each key during the last successful
+    /// `collect()` that read the environment, such as `/run/secrets/db.json` for `db`.
+    ///
+    /// Keys are the ones inserted by the source, before `post_process`: those of a file loaded
+    /// at the root are its top-level keys. Keys provided inline, by a variable or with
+    /// `with_inline_secrets`, have no path and are left out. Values are never recorded.
+    pub fn provenance(&self) -> Map<String, PathBuf> {
+        self.provenance.get()
+    }
+
+    /// Returns the resolved path of the secret file that provided the secret of `value`, the
+    /// last existing one of a path list.
+    fn provided_by(&self, value: &str) -> Option<PathBuf> {
+        if self.inline_content(value).is_some() {
+            return None;
+        }
+
+        self.paths(value)
+            .iter()
+            .rev()
+            .map(|path| self.options.resolve_path(path).into_owned())
+            .find(|path| self.options.exists(path))
+    }
+
+    /// Returns the paths of the secret files listed in `value`.
+    fn paths(&self, value: &str) -> Vec<PathBuf> {
+        if self.path_list {
+            env::split_paths(value).collect()
+        } else {
+            vec![PathBuf::from(value)]
+        }
+    }
+
+    /// Returns the content carried by `value` when it starts with the content prefix.
+    ///
+    /// A byte order mark or whitespace before the marker is ignored.
+    fn inline_content<'a>(&self, value: &'a str) -> Option<&'a str> {
+        let value = value.trim_start_matches(BOM).trim_start();
+        value.strip_prefix(self.content_prefix.as_deref()?)
+    }
+
+    /// Returns the resolved paths of the secret files listed in the value of the variable
+    /// `name`, for origins.
+    fn origin_path(&self, name: &str, value: &str) -> String {
+        if self.inline_content(value).is_some() {
+            return format!("env:{}", name);
+        }
+
+        self.paths(value)
+            .iter()
+            .map(|path| self.options.resolve_path(path).display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Loads and merges the secret files listed in `value`, `None` meaning that none exists.
+    fn load_paths(
+        &self,
+        name: &str,
+        key: &str,
+        value: &str,
+        required: bool,
+        stats: &mut CollectStats,
+    ) -> Result<Option<Value>, SecretError> {
+        let options = self.options.for_key(key);
+
+        if let Some(content) = self.inline_content(value) {
+            let uri = format!("env:{}", name);
+            let loaded = parse_value(Path::new(name), &uri, content, &options);
+            return loaded.map(Some).map_err(|source| SecretError::Inline {
+                var: name.to_string(),
+                source: Box::new(source),
+            });
+        }
+
+        let mut merged: Option<Value> = None;
+
+        for path in self.paths(value) {
+            let loaded =
+                load_optional(&path, &options, required).map_err(|source| SecretError::Var {
+                    var: name.to_string(),
+                    path: path.clone(),
+                    source: Box::new(source),
+                })?;
+            let loaded = match loaded {
+                Some((loaded, bytes)) => {
+                    stats.record(bytes);
+                    loaded
+                }
+                None => continue,
+            };
+
+            match merged {
+                Some(ref mut merged) => merge_value(merged, loaded),
+                None => merged = Some(loaded),
+            }
+        }
+
+        Ok(merged)
+    }
+
+    /// Checks that the options of this source are coherent.
+    ///
+    /// The following rules are enforced:
+    ///
+    /// - `prefix` must not be empty, leave it unset to match variables without prefix,
+    /// - an empty `suffix` requires a `prefix` or `match_all(true)`, otherwise every variable
+    ///   of the environment would be read as a secret path,
+    /// - `suffix_separator` requires suffix matching,
+    /// - `prefix_separator` and `keep_prefix(true)` require a `prefix`,
+    /// - `keep_prefix_case(true)` requires `keep_prefix(true)`,
+    /// - `separator` must not be empty, leave it unset to keep keys flat,
+    /// - `indirection_prefix` must not be empty, it would match every value.
+    pub fn validate(&self) -> Result<(), SecretError> {
+        let invalid = |reason: &str| Err(SecretError::InvalidOptions(reason.to_string()));
+
+        if self.prefix.as_deref() == Some("") {
+            return invalid("`prefix` is empty");
+        }
+        self.check_matching()?;
+        if self.suffix.as_deref() == Some("") && self.suffix_separator.is_some() {
+            return invalid("`suffix_separator` is set while the suffix is disabled");
+        }
+        if self.prefix.is_none() && self.prefix_separator.is_some() {
+            return invalid("`prefix_separator` is set without a `prefix`");
+        }
+        if self.prefix.is_none() && self.keep_prefix {
+            return invalid("`keep_prefix` is set without a `prefix`");
+        }
+        if !self.keep_prefix && self.keep_prefix_case {
+            return invalid("`keep_prefix_case` is set without `keep_prefix`");
+        }
+        if self.separator.as_deref() == Some("") {
+            return invalid("`separator` is empty");
+        }
+        if self.indirection_prefix.as_deref() == Some("") {
+            return invalid("`indirection_prefix` is empty");
+        }
+
+        Ok(())
+    }
+
+    /// Rejects sources that would match every variable of the environment.
+    fn check_matching(&self) -> Result<(), SecretError> {
+        if self.prefix.is_none() && self.suffix.as_deref() == Some("") && !self.match_all {
+            return Err(SecretError::InvalidOptions(
+                "neither a `prefix` nor a `suffix` constrains matching, set `match_all(true)` to \
+                 read every variable"
+                    .to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn var_filter(mut self, f: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        self.var_filter = Some(Hook::new(Arc::new(f)));
+        self
+    }
+
+    pub fn resolver(
+        mut self,
+        f: impl Fn(&str) -> Result<Option<Value>, SecretError> + Send + Sync + 'static,
+    ) -> Self {
+        self.resolver = Some(Hook::new(Arc::new(f)));
+        self
+    }
+
+    /// Returns the value supplied by the resolver for `key`, if any.
+    fn resolve(&self, key: &str) -> Result<Option<Value>, SecretError> {
+        match self.resolver {
+            Some(ref resolver) => resolver(key),
+            None => Ok(None),
+        }
+    }
+}
+
+impl EnvironmentSecretFile {
+    /// Computes the patterns used to match environment variables.
+    fn patterns(&self) -> Patterns {
+        let separator = self.separator.clone().unwrap_or_default();
+        let key_delimiter = self.key_delimiter.clone().unwrap_or_else(|| ".".into());
+        let prefix_separator = match (self.prefix_separator.as_deref(), self.separator.as_deref()) {
+            (Some(pre), _) => pre,
+            (None, Some(sep)) => sep,
+            (None, None) => "_",
+        };
+        let suffix_separator = match (self.suffix_separator.as_deref(), self.separator.as_deref()) {
+            (Some(suf), _) => suf,
+            (None, Some(sep)) => sep,
+            (None, None) => "_",
+        };
+
+        let prefix_pattern = self
+            .prefix
+            .as_ref()
+            .map(|prefix| format!("{}{}", prefix, prefix_separator).to_lowercase());
+
+        let suffix = self.suffix.as_ref().map_or_else(|| "FILE", |s| s.as_str());
+        let suffix_front = self.marker_position == Position::Prefix;
+        let suffix_pattern = if suffix.is_empty() {
+            String::new()
+        } else if suffix_front {
+            format!("{}{}", suffix, suffix_separator).to_lowercase()
+        } else {
+            format!("{}{}", suffix_separator, suffix).to_lowercase()
+        };
+
+        let full_pattern = if let Some(prefix) = self.prefix.as_ref() {
+            if suffix.is_empty() {
+                prefix.to_lowercase()
+            } else if prefix_separator.is_empty() {
+                // The prefix is glued to the key, which is empty for the whole file
+                format!("{}{}{}", prefix, suffix_separator, suffix).to_lowercase()
+            } else if prefix_separator == suffix_separator {
+                format!("{}{}{}", prefix, prefix_separator, suffix).to_lowercase()
+            } else {
+                format!("{}{}", prefix, suffix)
+            }
+        } else {
+            suffix.to_string()
+        };
+
+        Patterns {
+            separator,
+            key_delimiter,
+            prefix_pattern,
+            suffix_pattern,
+            suffix_front,
+            full_pattern,
+        }
+    }
+
+    /// Matches the environment variable `name` against `patterns`.
+    fn match_name(&self, patterns: &Patterns, name: &str) -> Matched {
+        let original = name;
+        let name = self.normalize_separators(patterns, name);
+        let name = name.as_ref();
+
+        // Most variables of a large environment do not match, skip them before allocating
+        if patterns.rejects(name) {
+            return Matched::None;
+        }
+
+        let mut key = name.to_lowercase();
+
+        if key == patterns.full_pattern {
+            return Matched::Root;
+        }
+
+        // Positions in the key are the ones in the name unless normalizing changed lengths
+        let aligned = key.len() == name.len() && name.len() == original.len();
+
+        // Check for prefix
+        let mut prefixed = false;
+        let mut removed = 0;
+        if let Some(ref prefix_pattern) = patterns.prefix_pattern {
+            if key.starts_with(prefix_pattern) {
+                prefixed = true;
+                if !self.keep_prefix {
+                    // Remove this prefix from the key
+                    key = key[prefix_pattern.len()..].to_string();
+                    removed = prefix_pattern.len();
+                } else if self.keep_prefix_case {
+                    // Restore the original case of the prefix
+                    if let Some(original) = name.get(..prefix_pattern.len()) {
+                        key = format!("{}{}", original, &key[prefix_pattern.len()..]);
+                    }
+                }
+            } else if !patterns.suffix_pattern.is_empty() && patterns.has_suffix(&key, 0) {
+                if self.suffix_rule != SuffixRule::WithoutPrefix {
+                    return Matched::SuffixOnly;
+                }
+            } else {
+                // Skip this key
+                return Matched::None;
+            }
+        }
+
+        // Check for suffix, which follows the kept prefix when in front
+        let start = match patterns.prefix_pattern {
+            Some(ref prefix_pattern) if self.keep_prefix => prefix_pattern.len(),
+            _ => 0,
+        };
+        let mut suffix = None;
+        if patterns.has_suffix(&key, start) {
+            let len = patterns.suffix_pattern.len();
+            let at = if patterns.suffix_front {
+                start
+            } else {
+                key.len() - len
+            };
+            if aligned {
+                suffix = Some(removed + at..removed + at + len);
+            }
+
+            // Remove this suffix from the key
+            key = patterns.strip_suffix(&key, start);
+        } else if !(prefixed && self.suffix_rule == SuffixRule::WithoutPrefix && key.len() > start)
+        {
+            // Skip this key
+            return Matched::PrefixOnly;
+        }
+
+        // If separator is given replace with the key delimiter
+        if !patterns.separator.is_empty() {
+            key = key.replace(&patterns.separator, &patterns.key_delimiter);
+        }
+
+        let key = self.key_case.apply_nested(&key, &patterns.key_delimiter);
+        let key = match self.scope {
+            Some(ref scope) => format!("{}{}{}", scope, patterns.key_delimiter, key),
+            None => key,
+        };
+        Matched::Key(key, suffix)
+    }
+
+    /// Replaces the alternate separators of `name` with `separator`.
+    fn normalize_separators<'a>(&self, patterns: &Patterns, name: &'a str) -> Cow<'a, str> {
+        let mut name = Cow::Borrowed(name);
+        if patterns.separator.is_empty() {
+            return name;
+        }
+
+        for alternate in &self.alternate_separators {
+            if !alternate.is_empty() && name.contains(alternate.as_str()) {
+                name = Cow::Owned(name.replace(alternate.as_str(), &patterns.separator));
+            }
+        }
+        name
+    }
+
+    /// Returns the names of the variables that would be loaded under `key`.
+    ///
+    /// Every spelling accepted by the matching is listed: with and without the suffix or the
+    /// prefix when `suffix_required_when(...)` allows it, and with each of the `separators`.
+    /// Keys converted by `key_case` or outside of the `scope` cannot be traced back to a
+    /// variable and give none.
+    fn expected_vars(&self, patterns: &Patterns, key: &str) -> Vec<String> {
+        if self.key_case != KeyCase::AsIs {
+            return Vec::new();
+        }
+
+        let full_key = key;
+        let key = match self.scope {
+            Some(ref scope) => {
+                let scope = format!("{}{}", scope, patterns.key_delimiter);
+                match key.strip_prefix(&scope) {
+                    Some(key) => key,
+                    None => return Vec::new(),
+                }
+            }
+            None => key,
+        };
+
+        let key = if patterns.separator.is_empty() {
+            key.to_string()
+        } else {
+            key.replace(&patterns.key_delimiter, &patterns.separator)
+        };
+
+        let prefix = if self.keep_prefix {
+            ""
+        } else {
+            patterns.prefix_pattern.as_deref().unwrap_or_default()
+        };
+        let with_suffix = |prefix: &str| {
+            if patterns.suffix_front {
+                format!("{}{}{}", prefix, patterns.suffix_pattern, key)
+            } else {
+                format!("{}{}{}", prefix, key, patterns.suffix_pattern)
+            }
+        };
+
+        let mut spellings = vec![with_suffix(prefix)];
+        if self.suffix_rule == SuffixRule::WithoutPrefix {
+            spellings.push(format!("{}{}", prefix, key));
+            spellings.push(with_suffix(""));
+        }
+        if !patterns.separator.is_empty() {
+            for alternate in &self.alternate_separators {
+                let alternates: Vec<_> = spellings
+                    .iter()
+                    .filter(|name| !name.contains(alternate.as_str()))
+                    .map(|name| name.replace(&patterns.separator, alternate))
+                    .collect();
+                spellings.extend(alternates);
+            }
+        }
+
+        let mut names = Vec::new();
+        for name in spellings {
+            let name = name.to_uppercase();
+            let matched = match self.match_name(patterns, &name) {
+                Matched::Key(matched, _) => matched == full_key,
+                _ => false,
+            };
+            if matched && self.accepts(&name) && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Whether the variable `name` passes the user filters.
+    fn accepts(&self, name: &str) -> bool {
+        if !self.ignore_vars.is_empty() && self.ignore_vars.contains(&name.to_lowercase()) {
+            return false;
+        }
+
+        match self.var_filter {
+            Some(ref var_filter) => var_filter(name),
+            None => true,
+        }
+    }
+
+    /// Applies the transform of `key` to the value loaded from the variable `name`, if any.
+    fn transform(
+        &self,
+        name: &str,
+        path: &str,
+        key: &str,
+        value: Value,
+    ) -> Result<Value, SecretError> {
+        match self.value_transforms.get(key) {
+            Some(transform) => transform(value).map_err(|source| SecretError::Var {
+                var: name.into(),
+                path: path.into(),
+                source: Box::new(source),
+            }),
+            None => Ok(value),
+        }
+    }
+
+    /// Warns when the inline value of the variable `name` is the path of an existing file,
+    /// which usually means that it was meant to be set on its `file_name` variant.
+    #[allow(unused_variables)]
+    fn warn_path_like(&self, name: &str, value: &str, file_name: &str) {
+        #[cfg(feature = "tracing")]
+        {
+            let path = Path::new(value.trim_start_matches(BOM).trim());
+            if path.is_absolute() && self.options.exists(path) {
+                log_warn!(
+                    "environment variable {} holds the path of an existing file, it may be meant \
+                     for {}",
+                    name,
+                    file_name
+                );
+            }
+        }
+    }
+
+    /// Whether the value of a matched variable should be treated as unset.
+    fn is_unset(&self, value: &str) -> bool {
+        value.is_empty() || (self.trim_value_check && value.trim().is_empty())
+    }
+
+    /// Builds a `Config` made of this source only.
+    pub fn into_config(self) -> Result<Config, ConfigError> {
+        Config::builder().add_source(self).build()
+    }
+
+    /// Builds a `Config` made of this source only and deserializes it.
+    pub fn load<T: DeserializeOwned>(self) -> Result<T, ConfigError> {
+        self.into_config()?.try_deserialize()
+    }
+
+    /// Loads and parses every matched secret like `collect()` then drops them, returning the
+    /// first error met, for readiness probes that must not retain secret values.
+    ///
+    /// The cache is bypassed, so that secrets are read again at each check, while
+    /// `last_stats()` and `provenance()` are updated as by a `collect()`.
+    pub fn check(&self) -> Result<(), SecretError> {
+        match self.collect_secrets() {
+            Ok(_) => Ok(()),
+            Err(ConfigError::Foreign(err)) => match err.downcast::<SecretError>() {
+                Ok(err) => Err(*err),
+                Err(err) => Err(SecretError::Config(ConfigError::Foreign(err))),
+            },
+            Err(err) => Err(SecretError::Config(err)),
+        }
+    }
+
+    /// Lists the variables of the environment that are likely misconfigured.
+    ///
+    /// It only looks at variable names and emptiness, no file is read.
+    pub fn audit(&self) -> AuditReport {
+        let patterns = self.patterns();
+        let mut report = AuditReport::default();
+
+        // An invalid manifest is reported by `collect()`
+        for (name, value, matched) in self.candidates(&patterns).unwrap_or_default() {
+            match matched {
+                Matched::Root | Matched::Key(..) if self.is_unset(&value) => {
+                    report.empty.push(name)
+                }
+                Matched::PrefixOnly => report.prefix_without_suffix.push(name),
+                Matched::SuffixOnly => report.suffix_without_prefix.push(name),
+                _ => {}
+            }
+        }
+
+        report
+    }
+
+    /// Describes how this source interprets the environment, for diagnostics.
+    ///
+    /// The description lists the effective patterns and separators, then the variables that
+    /// match, partially match or are rejected by the filters. Only variable names are shown,
+    /// never their values, and no file is read.
+    pub fn explain(&self) -> String {
+        let patterns = self.patterns();
+        let mut matched = Vec::new();
+        let mut empty = Vec::new();
+        let mut prefix_only = Vec::new();
+        let mut suffix_only = Vec::new();
+        let mut rejected = Vec::new();
+        let mut others = 0;
+
+        let (candidates, manifest_error) = match self.candidates(&patterns) {
+            Ok(candidates) => (candidates, None),
+            Err(err) => (Vec::new(), Some(err)),
+        };
+        for (name, value, outcome) in candidates {
+            match outcome {
+                Matched::Root | Matched::Key(..) if self.is_unset(&value) => empty.push(name),
+                Matched::Root => matched.push(format!("{} -> (root)", name)),
+                Matched::Key(key, _) | Matched::Listed(key) if self.seed_keys.contains(&key) => {
+                    matched.push(format!("{} -> {} (seed key, skipped)", name, key))
+                }
+                Matched::Key(key, _) => matched.push(format!("{} -> {}", name, key)),
+                Matched::Listed(key) => matched.push(format!("{} -> {} (manifest)", name, key)),
+                Matched::PrefixOnly => prefix_only.push(name),
+                Matched::SuffixOnly => suffix_only.push(name),
+                Matched::Rejected => rejected.push(name),
+                Matched::None => others += 1,
+            }
+        }
+
+        let mut out = String::new();
+        if let Err(err) = self.validate() {
+            let _ = writeln!(out, "invalid options: {}", err);
+        }
+        if let Some(err) = manifest_error {
+            let _ = writeln!(out, "invalid manifest: {}", err);
+        }
+
+        let _ = writeln!(out, "patterns are matched case-insensitively");
+        match patterns.prefix_pattern {
+            Some(ref prefix_pattern) => {
+                let _ = writeln!(out, "prefix pattern: {:?}", prefix_pattern);
+            }
+            None => {
+                let _ = writeln!(out, "prefix pattern: none");
+            }
+        }
+        match (patterns.suffix_pattern.is_empty(), patterns.suffix_front) {
+            (true, _) => {
+                let _ = writeln!(out, "suffix pattern: none");
+            }
+            (false, true) => {
+                let _ = writeln!(
+                    out,
+                    "suffix pattern: {:?}, before the key",
+                    patterns.suffix_pattern
+                );
+            }
+            (false, false) => {
+                let _ = writeln!(
+                    out,
+                    "suffix pattern: {:?}, after the key",
+                    patterns.suffix_pattern
+                );
+            }
+        }
+        let _ = writeln!(out, "root pattern: {:?}", patterns.full_pattern);
+        if patterns.separator.is_empty() {
+            let _ = writeln!(out, "separator: none, keys are not nested");
+        } else {
+            let _ = writeln!(
+                out,
+                "separator: {:?}, replaced with the key delimiter {:?}",
+                patterns.separator, patterns.key_delimiter
+            );
+        }
+
+        let sections = [
+            ("matched", matched),
+            ("matched but empty", empty),
+            ("prefix without suffix", prefix_only),
+            ("suffix without prefix", suffix_only),
+            ("rejected by filters", rejected),
+        ];
+        for (title, mut names) in sections {
+            if names.is_empty() {
+                continue;
+            }
+
+            names.sort();
+            let _ = writeln!(out, "{}:", title);
+            for name in names {
+                let _ = writeln!(out, "  {}", name);
+            }
+        }
+        let _ = writeln!(out, "other variables: {}", others);
+
+        out
+    }
+
+    /// Lists the config keys the matched variables would be loaded under, sorted.
+    ///
+    /// Only variable names are matched, no file is read. The keys of a file loaded at the root
+    /// come from its content and cannot be known this way: such a match is listed as an empty
+    /// key, and reading the source is the only way to get them.
+    pub fn keys(&self) -> Vec<String> {
+        let patterns = self.patterns();
+        let mut keys = Vec::new();
+
+        // An invalid manifest is reported by `collect()`
+        for (_, value, matched) in self.candidates(&patterns).unwrap_or_default() {
+            if self.is_unset(&value) {
+                continue;
+            }
+
+            match matched {
+                Matched::Root => keys.push(String::new()),
+                Matched::Key(key, _) | Matched::Listed(key) if !self.seed_keys.contains(&key) => {
+                    keys.push(key)
+                }
+                _ => {}
+            }
+        }
+
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    /// Whether a required secret file is missing, or matched variables point at secret files
+    /// and none of them exists yet.
+    ///
+    /// Variables whose value cannot be resolved, and an invalid manifest, are left to
+    /// `collect()` to report.
+    fn awaits_files(&self, patterns: &Patterns) -> bool {
+        let (mut found, mut missing) = (false, false);
+
+        for (name, value, matched) in self.candidates(patterns).unwrap_or_default() {
+            if self.is_unset(&value) {
+                continue;
+            }
+
+            let (required, value) = match matched {
+                Matched::Root => (!self.optional, self.resolve_path(&name, value)),
+                Matched::Key(key, _) if !self.seed_keys.contains(&key) => (
+                    !self.optional && !self.optional_keys.contains(&key),
+                    self.resolve_path(&name, value),
+                ),
+                // The paths of a manifest are not indirections
+                Matched::Listed(key) if !self.seed_keys.contains(&key) => (
+                    !self.optional && !self.optional_keys.contains(&key),
+                    Ok(value),
+                ),
+                _ => continue,
+            };
+
+            let value = match value {
+                Ok(value) if self.inline_content(&value).is_none() => value,
+                _ => continue,
+            };
+            for path in self.paths(&value) {
+                if self.options.exists(&self.options.resolve_path(&path)) {
+                    found = true;
+                } else if required {
+                    return true;
+                } else {
+                    missing = true;
+                }
+            }
+        }
+
+        missing && !found
+    }
+
+    /// Waits up to `period` for the secret files of the matched variables to appear.
+    fn wait_for_files(&self, patterns: &Patterns, period: Duration) {
+        let deadline = Instant::now() + period;
+
+        while self.awaits_files(patterns) {
+            let now = Instant::now();
+            if now >= deadline {
+                return;
+            }
+            thread::sleep(WAIT_INTERVAL.min(deadline - now));
+        }
+    }
+
+    /// Collects the secrets of the environment, bypassing the cache.
+    ///
+    /// Nothing outside of the returned map is updated before every check has passed.
+    fn collect_secrets(&self) -> Result<Map<String, Value>, ConfigError> {
+        self.check_matching()?;
+
+        let mut m = Map::new();
+        if let Some((ref gate, ref expected)) = self.enable_if {
+            if self.vars().var(gate).as_ref() != Some(expected) {
+                return Ok(m);
+            }
+        }
+
+        let patterns = self.patterns();
+        if let Some(period) = self.wait_for {
+            self.wait_for_files(&patterns, period);
+        }
+
+        let mut stats = CollectStats::default();
+        let mut root = Map::new();
+        let mut provenance = Map::new();
+
+        #[cfg(feature = "json")]
+        for (key, path) in self.manifest()? {
+            let name = self.manifest_var.as_deref().unwrap_or_default();
+            if self.seed_keys.contains(&key) {
+                continue;
+            }
+            if let Some(resolved) = self.resolve(&key)? {
+                let resolved = self.transform(name, &path, &key, resolved)?;
+                m.insert(key, resolved);
+                continue;
+            }
+
+            let required = !self.optional && !self.optional_keys.contains(&key);
+            let loaded = match self.load_paths(name, &key, &path, required, &mut stats)? {
+                Some(loaded) => loaded,
+                None => continue,
+            };
+
+            let origin_path = self.origin_path(name, &path);
+            let loaded = self.options.label(loaded, &key, &path, &origin_path);
+            let loaded = self.transform(name, &origin_path, &key, loaded)?;
+            if let Some(provided) = self.provided_by(&path) {
+                provenance.insert(key.clone(), provided);
+            }
+            m.insert(key, loaded);
+        }
+
+        for (name, value) in self.matched_vars() {
+            // Skip variables rejected by the user
+            if !self.accepts(&name) {
+                continue;
+            }
+
+            // Treat empty environment variables as unset
+            if self.is_unset(&value) {
+                continue;
+            }
+
+            let (key, suffix) = match self.match_name(&patterns, &name) {
+                Matched::Root => {
+                    let value = self.resolve_path(&name, value)?;
+                    let loaded = self.load_paths(&name, "", &value, !self.optional, &mut stats)?;
+                    if let Some(loaded) = loaded {
+                        let origin_path = self.origin_path(&name, &value);
+                        let loaded = self.options.label(loaded, "", &value, &origin_path);
+                        let map = loaded.into_table().map_err(|err| SecretError::Var {
+                            var: name.clone(),
+                            path: origin_path.as_str().into(),
+                            source: Box::new(err.into()),
+                        })?;
+                        let map = match self.key_field {
+                            Some(ref field) => {
+                                let key = map
+                                    .get(field)
+                                    .and_then(|value| value.clone().into_string().ok())
+                                    .ok_or_else(|| SecretError::KeyField {
+                                        var: name.clone(),
+                                        path: origin_path.as_str().into(),
+                                        field: field.clone(),
+                                    })?;
+                                let value = Value::new(Some(&origin_path), ValueKind::Table(map));
+                                Some((key, value)).into_iter().collect()
+                            }
+                            None => map,
+                        };
+                        let provided = self.provided_by(&value);
+                        for (key, value) in map {
+                            if !self.seed_keys.contains(&key) {
+                                let value = self.transform(&name, &origin_path, &key, value)?;
+                                if let Some(ref provided) = provided {
+                                    provenance.insert(key.clone(), provided.clone());
+                                }
+                                root.insert(key, value);
+                            }
+                        }
+                    }
+                    continue;
+                }
+                Matched::Key(key, suffix) if !self.seed_keys.contains(&key) => (key, suffix),
+                _ => continue,
+            };
+
+            if let Some(resolved) = self.resolve(&key)? {
+                let resolved = self.transform(&name, &value, &key, resolved)?;
+                m.insert(key, resolved);
+                continue;
+            }
+
+            let value = self.resolve_path(&name, value)?;
+            let required = !self.optional && !self.optional_keys.contains(&key);
+            let mut loaded = match self.load_paths(&name, &key, &value, required, &mut stats)? {
+                Some(loaded) => loaded,
+                None => continue,
+            };
+
+            if self.merge_inline {
+                // The inline variable is the matched one without its suffix, only variables
+                // matched with their suffix have one
+                let inline_name = suffix.and_then(|suffix| {
+                    Some(format!(
+                        "{}{}",
+                        name.get(..suffix.start)?,
+                        name.get(suffix.end..)?
+                    ))
+                });
+                let inline = inline_name
+                    .as_deref()
+                    .and_then(|n| self.vars().var(n).map(|v| (n, v)));
+
+                if let Some((inline_name, inline)) = inline.filter(|(_, v)| !self.is_unset(v)) {
+                    self.warn_path_like(inline_name, &inline, &name);
+                    let uri = format!("env:{}", inline_name);
+                    let path = self.paths(&value).pop().unwrap_or_default();
+                    let over = parse_value(&path, &uri, &inline, &self.options.for_key(&key))
+                        .map_err(|source| SecretError::Inline {
+                            var: inline_name.to_string(),
+                            source: Box::new(source),
+                        })?;
+                    merge_value(&mut loaded, over);
+                }
+            }
+
+            let origin_path = self.origin_path(&name, &value);
+            let loaded = self.options.label(loaded, &key, &value, &origin_path);
+            let loaded = self.transform(&name, &origin_path, &key, loaded)?;
+            if let Some(provided) = self.provided_by(&value) {
+                provenance.insert(key.clone(), provided);
+            }
+            m.insert(key, loaded);
+        }
+
+        // The secrets of their own variable override the ones of the whole file
+        let keyed = std::mem::replace(&mut m, root);
+        for (key, value) in keyed {
+            if self.dual_keys && key.contains(&patterns.key_delimiter) {
+                let flat = key.replace(&patterns.key_delimiter, "_");
+                merge_at(&mut m, &flat, value.clone(), &patterns.key_delimiter);
+            }
+            merge_at(&mut m, &key, value, &patterns.key_delimiter);
+        }
+
+        if !self.inline_secrets.0.is_empty() {
+            let inline = self.inline_secrets.0.clone();
+            if self.inline_secrets_override {
+                merge_map(&mut m, inline);
+            } else {
+                let loaded = std::mem::replace(&mut m, inline);
+                merge_map(&mut m, loaded);
+            }
+        }
+
+        if self.flatten {
+            let mut flat = Map::new();
+            for (key, value) in m {
+                flatten_value(
+                    &mut flat,
+                    key,
+                    value,
+                    &patterns.key_delimiter,
+                    self.flatten_arrays,
+                );
+            }
+            m = flat;
+        }
+
+        if let Some(ref post_process) = self.post_process {
+            post_process(&mut m);
+        }
+
+        let missing: Vec<_> = self
+            .require_keys
+            .iter()
+            .filter(|key| !contains_key(&m, key, &patterns.key_delimiter))
+            .map(|key| (key.clone(), self.expected_vars(&patterns, key)))
+            .collect();
+        if !missing.is_empty() {
+            return Err(SecretError::MissingKeys { missing }.into());
+        }
+
+        if self.require_nonempty && m.is_empty() {
+            return Err(SecretError::NoSecrets.into());
+        }
+
+        self.stats.set(stats);
+        self.provenance.set(provenance);
+        Ok(m)
+    }
+}
+
+/// Whether `name` starts with `pattern`, ignoring ASCII case.
+fn starts_with_ignore_case(name: &str, pattern: &str) -> bool {
+    name.len() >= pattern.len()
+        && name.as_bytes()[..pattern.len()].eq_ignore_ascii_case(pattern.as_bytes())
+}
+
+/// Whether `name` ends with `pattern`, ignoring ASCII case.
+fn ends_with_ignore_case(name: &str, pattern: &str) -> bool {
+    name.len() >= pattern.len()
+        && name.as_bytes()[name.len() - pattern.len()..].eq_ignore_ascii_case(pattern.as_bytes())
+}
+
+/// Whether `key` is in `map`, as is or nested with `delimiter` in its tables.
+fn contains_key(map: &Map<String, Value>, key: &str, delimiter: &str) -> bool {
+    if map.contains_key(key) {
+        return true;
+    }
+
+    key.match_indices(delimiter).any(|(index, _)| {
+        let (head, tail) = (&key[..index], &key[index + delimiter.len()..]);
+        match map.get(head).map(|value| &value.kind) {
+            Some(ValueKind::Table(table)) => contains_key(table, tail, delimiter),
+            _ => false,
+        }
+    })
+}
+
+/// Deep-merges `value` over the value of `map` at the `delimiter` separated `key`, nested in
+/// a table of `map` when one is found along the key.
+fn merge_at(map: &mut Map<String, Value>, key: &str, value: Value, delimiter: &str) {
+    if let Some(existing) = map.get_mut(key) {
+        return merge_value(existing, value);
+    }
+
+    let split = key
+        .match_indices(delimiter)
+        .map(|(index, _)| index)
+        .find(|&index| {
+            matches!(
+                map.get(&key[..index]).map(|value| &value.kind),
+                Some(ValueKind::Table(_))
+            )
+        });
+    match split {
+        Some(index) => {
+            let tail = &key[index + delimiter.len()..];
+            if let Some(ValueKind::Table(table)) = map.get_mut(&key[..index]).map(|v| &mut v.kind) {
+                merge_at(table, tail, value, delimiter);
+            }
+        }
+        None => {
+            map.insert(key.to_string(), value);
+        }
+    }
+}
+
+/// Patterns derived from the options of a source.
+struct Patterns {
+    separator: String,
+    key_delimiter: String,
+    prefix_pattern: Option<String>,
+    suffix_pattern: String,
+    suffix_front: bool,
+    full_pattern: String,
+}
+
+impl Patterns {
+    /// Whether `name` can neither match nor be reported as a partial match, checked without
+    /// allocating. Only ASCII names are checked, others going through the full matching.
+    fn rejects(&self, name: &str) -> bool {
+        let prefix_pattern = match self.prefix_pattern {
+            Some(ref prefix_pattern) if name.is_ascii() => prefix_pattern,
+            _ => return false,
+        };
+
+        // The patterns are lowercase, so comparing them with an ASCII name ignoring ASCII case
+        // gives the same result as comparing them with the lowercased name
+        let has_suffix = !self.suffix_pattern.is_empty()
+            && if self.suffix_front {
+                starts_with_ignore_case(name, &self.suffix_pattern)
+            } else {
+                ends_with_ignore_case(name, &self.suffix_pattern)
+            };
+
+        !name.eq_ignore_ascii_case(&self.full_pattern)
+            && !starts_with_ignore_case(name, prefix_pattern)
+            && !has_suffix
+    }
+
+    /// Whether `key` has the suffix, in front starting at `start` or at the end.
+    fn has_suffix(&self, key: &str, start: usize) -> bool {
+        if self.suffix_front {
+            matches!(key.get(start..), Some(rest) if rest.starts_with(&self.suffix_pattern))
+        } else {
+            key.ends_with(&self.suffix_pattern)
+        }
+    }
+
+    /// Removes the suffix from `name`, which must have it at the same place as in `has_suffix`.
+    fn strip_suffix(&self, name: &str, start: usize) -> String {
+        let len = self.suffix_pattern.len();
+        if self.suffix_front {
+            format!("{}{}", &name[..start], &name[start + len..])
+        } else {
+            name[..name.len() - len].to_string()
+        }
+    }
+}
+
+/// Secrets provided in process, whose `Debug` output lists the keys but never the values.
+#[derive(Clone, Default)]
+struct InlineSecrets(Map<String, Value>);
+
+impl fmt::Debug for InlineSecrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+/// Outcome of matching an environment variable name.
+enum Matched {
+    /// The variable points at a file loaded at the root of the config.
+    Root,
+
+    /// The variable points at a file loaded under the derived key, with the range of the
+    /// suffix in its name when it has one at a known position.
+    Key(String, Option<Range<usize>>),
+
+    /// The manifest lists the file loaded under this key.
+    #[cfg_attr(not(feature = "json"), allow(dead_code))]
+    Listed(String),
+
+    /// The variable matches but is rejected by the filters.
+    Rejected,
+
+    /// The variable has the prefix but not the suffix.
+    PrefixOnly,
+
+    /// The variable has the suffix but not the prefix.
+    SuffixOnly,
+
+    /// The variable is not related to this source.
+    None,
+}
+
+impl Source for EnvironmentSecretFile {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new((*self).clone())
+    }
+
+    /// Loads every matched secret, or none.
+    ///
+    /// Secrets are gathered in a map of their own which is only returned, cached and counted
+    /// in `last_stats()` once all of them are loaded and the required keys are checked. A
+    /// failure returns the error alone and leaves the cache and the statistics untouched.
+    ///
+    /// The file of the root variable, such as `APP_FILE`, is the base over which the secrets
+    /// of the other variables, such as `APP_DB_PASSWORD_FILE`, are deep-merged: a secret of its
+    /// own variable always wins over the same value of the whole file.
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        if self.cache {
+            self.cached.get_or_try_insert(|| self.collect_secrets())
+        } else {
+            self.collect_secrets()
         }
     }
 }
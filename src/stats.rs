@@ -0,0 +1,56 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use config::Map;
+
+/// Counters of the files read during a collect.
+///
+/// Only counts and sizes are recorded, never contents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CollectStats {
+    /// Number of secret files read.
+    pub files: usize,
+
+    /// Total size in bytes of the contents parsed, as read from the secret files once
+    /// decrypted.
+    pub bytes: u64,
+}
+
+impl CollectStats {
+    /// Records that a secret file of `bytes` bytes was read.
+    pub(crate) fn record(&mut self, bytes: u64) {
+        self.files += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// Statistics of the last collect, shared between the clones of a source.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StatsCell(Arc<Mutex<CollectStats>>);
+
+impl StatsCell {
+    pub(crate) fn get(&self) -> CollectStats {
+        *self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    pub(crate) fn set(&self, stats: CollectStats) {
+        *self.0.lock().unwrap_or_else(|err| err.into_inner()) = stats;
+    }
+}
+
+/// Paths of the files that provided each key during the last collect, shared between the
+/// clones of a source.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ProvenanceCell(Arc<Mutex<Map<String, PathBuf>>>);
+
+impl ProvenanceCell {
+    pub(crate) fn get(&self) -> Map<String, PathBuf> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+
+    pub(crate) fn set(&self, provenance: Map<String, PathBuf>) {
+        *self.0.lock().unwrap_or_else(|err| err.into_inner()) = provenance;
+    }
+}
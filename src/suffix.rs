@@ -0,0 +1,19 @@
+/// Variables that must carry the suffix, such as `FILE`, to be matched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SuffixRule {
+    /// Every variable needs the suffix, `APP_DB` being skipped while `APP_DB_FILE` is read.
+    Always,
+
+    /// Only the variables without the prefix need the suffix: both `APP_DB` and `DB_FILE`
+    /// are read as the path of `db`, while `DB` is skipped.
+    ///
+    /// This accommodates environments migrating from one convention to the other.
+    WithoutPrefix,
+}
+
+#[allow(clippy::derivable_impls)] // `#[default]` on variants requires Rust 1.62
+impl Default for SuffixRule {
+    fn default() -> Self {
+        SuffixRule::Always
+    }
+}
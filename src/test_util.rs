@@ -0,0 +1,45 @@
+use std::collections::BTreeMap;
+
+use config::{Source, Value, ValueKind};
+
+/// Collects `source` into a flat map of strings, for asserting on loaded secrets in tests.
+///
+/// Nested keys are joined with `.` and array items are indexed as `key[0]`, the way `config`
+/// paths are written, so that `redis.nodes[0]` holds the first node. Nil values are empty
+/// strings.
+///
+/// # Panics
+///
+/// Panics with the error of the source when it fails to collect.
+pub fn collect_into_map(source: &impl Source) -> BTreeMap<String, String> {
+    let collected = source
+        .collect()
+        .unwrap_or_else(|err| panic!("failed to collect the secret source: {}", err));
+
+    let mut map = BTreeMap::new();
+    for (key, value) in collected {
+        flatten(key, value, &mut map);
+    }
+    map
+}
+
+fn flatten(key: String, value: Value, map: &mut BTreeMap<String, String>) {
+    match value.kind {
+        ValueKind::Table(table) => {
+            for (child, value) in table {
+                flatten(format!("{}.{}", key, child), value, map);
+            }
+        }
+        ValueKind::Array(values) => {
+            for (index, value) in values.into_iter().enumerate() {
+                flatten(format!("{}[{}]", key, index), value, map);
+            }
+        }
+        ValueKind::Nil => {
+            map.insert(key, String::new());
+        }
+        kind => {
+            map.insert(key, kind.to_string());
+        }
+    }
+}
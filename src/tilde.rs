@@ -0,0 +1,49 @@
+use std::path::{Path, PathBuf};
+
+/// Expands a leading `~` or `~user` of `path` to the matching home directory.
+///
+/// Paths without a leading tilde, or whose home directory cannot be determined, are
+/// returned unchanged.
+pub(crate) fn expand(path: &Path) -> PathBuf {
+    let text = match path.to_str() {
+        Some(text) if text.starts_with('~') => text,
+        _ => return path.to_path_buf(),
+    };
+
+    let (user, rest) = match text[1..].find(['/', std::path::MAIN_SEPARATOR]) {
+        Some(index) => (&text[1..index + 1], &text[index + 2..]),
+        None => (&text[1..], ""),
+    };
+
+    let home = if user.is_empty() {
+        home::home_dir()
+    } else {
+        user_home_dir(user)
+    };
+
+    match home {
+        Some(home) if rest.is_empty() => home,
+        Some(home) => home.join(rest),
+        None => path.to_path_buf(),
+    }
+}
+
+/// Looks up the home directory of `user` in the password database.
+#[cfg(unix)]
+fn user_home_dir(user: &str) -> Option<PathBuf> {
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+
+    passwd.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        if fields.len() >= 6 && fields[0] == user {
+            Some(PathBuf::from(fields[5]))
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(not(unix))]
+fn user_home_dir(_user: &str) -> Option<PathBuf> {
+    None
+}
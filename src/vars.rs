@@ -0,0 +1,132 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    fmt::{self, Debug},
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{dotenv, SecretError};
+
+/// Provides the variables matched by `EnvironmentSecretFile`.
+pub trait VarProvider: Debug + Send + Sync {
+    /// Returns every variable with its value.
+    fn vars(&self) -> Vec<(String, String)>;
+
+    /// Returns the value of the variable `name`, if set.
+    fn var(&self, name: &str) -> Option<String> {
+        self.vars()
+            .into_iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+}
+
+impl<T: VarProvider + ?Sized> VarProvider for Arc<T> {
+    fn vars(&self) -> Vec<(String, String)> {
+        (**self).vars()
+    }
+
+    fn var(&self, name: &str) -> Option<String> {
+        (**self).var(name)
+    }
+}
+
+/// Variables of the process environment, which is the default provider.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EnvVarProvider;
+
+impl VarProvider for EnvVarProvider {
+    fn vars(&self) -> Vec<(String, String)> {
+        env::vars().collect()
+    }
+
+    fn var(&self, name: &str) -> Option<String> {
+        env::var(name).ok()
+    }
+}
+
+/// Variables held in memory, whose `Debug` output lists the names but never the values.
+#[derive(Clone, Default)]
+pub struct MapVarProvider(BTreeMap<String, String>);
+
+impl MapVarProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_var(mut self, name: &str, value: &str) -> Self {
+        self.0.insert(name.into(), value.into());
+        self
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for MapVarProvider {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+impl Debug for MapVarProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MapVarProvider ")?;
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+impl VarProvider for MapVarProvider {
+    fn vars(&self) -> Vec<(String, String)> {
+        self.0
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
+    fn var(&self, name: &str) -> Option<String> {
+        self.0.get(name).cloned()
+    }
+}
+
+/// Variables read from a `.env` file when the provider is created.
+///
+/// See `MapVarProvider` for the in-memory counterpart, the file is not read again afterwards.
+#[derive(Clone, Debug)]
+pub struct DotenvVarProvider {
+    path: PathBuf,
+    vars: MapVarProvider,
+}
+
+impl DotenvVarProvider {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, SecretError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|source| SecretError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            vars: dotenv::parse(&text).into_iter().collect(),
+        })
+    }
+
+    /// Path of the `.env` file the variables were read from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl VarProvider for DotenvVarProvider {
+    fn vars(&self) -> Vec<(String, String)> {
+        self.vars.vars()
+    }
+
+    fn var(&self, name: &str) -> Option<String> {
+        self.vars.var(name)
+    }
+}
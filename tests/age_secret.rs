@@ -0,0 +1,57 @@
+#![cfg(all(feature = "age", feature = "json"))]
+
+use config_secret::{load_value, Options};
+
+mod helpers;
+use crate::helpers::{get_test_file, Settings};
+
+fn age_options(identity: &str) -> Options {
+    Options::new().age_identity(get_test_file(identity))
+}
+
+#[test]
+fn test_decrypt_file() {
+    let value = load_value(&get_test_file("config.json.age"), &age_options("age.key")).unwrap();
+    let settings = value.try_deserialize::<Settings>().unwrap();
+
+    assert!(settings.server.host == "0.0.0.0");
+    assert!(settings.server.port == 5000);
+}
+
+#[test]
+fn test_wrong_identity() {
+    let err = load_value(&get_test_file("config.json.age"), &age_options("other.key"))
+        .unwrap_err()
+        .to_string();
+
+    assert!(err.contains("failed to decrypt secret file"));
+    assert!(err.contains("config.json.age"));
+    assert!(!err.contains("0.0.0.0"));
+}
+
+#[test]
+fn test_missing_identity() {
+    let err = load_value(
+        &get_test_file("config.json.age"),
+        &age_options("missing.key"),
+    )
+    .unwrap_err()
+    .to_string();
+
+    assert!(err.contains("failed to read secret file"));
+    assert!(err.contains("missing.key"));
+}
+
+#[cfg(all(feature = "tar", feature = "gzip", feature = "yaml"))]
+#[test]
+fn test_decrypt_decompress_extract() {
+    use config::Source;
+    use config_secret::TarSecret;
+
+    let source =
+        TarSecret::new(get_test_file("secrets.tar.gz.age")).options(age_options("age.key"));
+    let map = source.collect().unwrap();
+
+    assert!(map.contains_key("server"));
+    assert!(map.contains_key("redis"));
+}
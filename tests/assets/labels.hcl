@@ -0,0 +1,3 @@
+database "primary" {
+  password = "secret"
+}
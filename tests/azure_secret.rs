@@ -0,0 +1,80 @@
+#![cfg(feature = "azure")]
+
+use config::{Config, Source};
+use config_secret::AzureKeyVaultSource;
+
+mod helpers;
+use crate::helpers::{http::serve, Settings};
+
+#[test]
+fn test_named_secret() {
+    let value = r#"{"value": "{\"server\": {\"host\": \"0.0.0.0\", \"port\": 5000}, \"redis\": {\"nodes\": []}}"}"#;
+    let (url, requests) = serve(vec![(200, value.to_string())]);
+
+    let source = AzureKeyVaultSource::new(&url, "app", "app-settings").access_token("token");
+    let config = Config::builder().add_source(source).build().unwrap();
+    let settings = config.get::<Settings>("app").unwrap();
+
+    assert!(settings.server.port == 5000);
+    assert!(requests.recv().unwrap() == "GET /secrets/app-settings?api-version=7.4 HTTP/1.1");
+}
+
+#[test]
+fn test_missing_secret() {
+    let not_found = r#"{"error": {"code": "SecretNotFound"}}"#.to_string();
+    let (url, _) = serve(vec![(404, not_found.clone()), (404, not_found)]);
+
+    let source = AzureKeyVaultSource::new(&url, "app", "missing").access_token("token");
+    assert!(source.collect().is_err());
+    assert!(source.required(false).collect().unwrap().is_empty());
+}
+
+#[test]
+fn test_prefix_enumeration() {
+    let listing = r#"{"value": [
+        {"id": "{url}/secrets/app-redis--password", "attributes": {"enabled": true}},
+        {"id": "{url}/secrets/other-token", "attributes": {"enabled": true}},
+        {"id": "{url}/secrets/app-disabled", "attributes": {"enabled": false}}
+    ], "nextLink": "{url}/secrets?api-version=7.4&$skiptoken=next"}"#;
+    let next = r#"{"value": [{"id": "{url}/secrets/app-db", "attributes": {}}], "nextLink": null}"#;
+
+    let (url, requests) = serve(vec![
+        (200, listing.to_string()),
+        (200, next.to_string()),
+        (200, r#"{"value": "postgres://db"}"#.to_string()),
+        (200, r#"{"value": "hunter2"}"#.to_string()),
+    ]);
+
+    let source = AzureKeyVaultSource::with_prefix(&url, "app-").access_token("token");
+    let map = source.collect().unwrap();
+
+    assert!(map.len() == 2);
+    assert!(map["db"].clone().into_string().unwrap() == "postgres://db");
+    assert!(map["redis.password"].clone().into_string().unwrap() == "hunter2");
+
+    let requests: Vec<_> = requests.iter().collect();
+    assert!(requests[1].contains("skiptoken=next"));
+    assert!(requests[2] == "GET /secrets/app-db?api-version=7.4 HTTP/1.1");
+}
+
+#[test]
+fn test_api_error() {
+    let (url, _) = serve(vec![(
+        401,
+        r#"{"error": {"code": "Unauthorized"}}"#.to_string(),
+    )]);
+
+    let source = AzureKeyVaultSource::new(&url, "app", "settings").access_token("expired");
+    let message = source.collect().unwrap_err().to_string();
+    assert!(message.contains("Unauthorized"));
+}
+
+#[test]
+fn test_debug_redacts_token() {
+    let source =
+        AzureKeyVaultSource::new("https://vault", "app", "settings").access_token("eyJ.secret");
+    let debug = format!("{:?}", source);
+
+    assert!(debug.contains("access_token: Some(\"<redacted>\")"));
+    assert!(!debug.contains("eyJ.secret"));
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "binary")]
+
+use config::Source;
+use config_secret::{load_value, EnvironmentSecretFile, Options};
+
+mod helpers;
+use crate::helpers::get_test_file;
+
+#[test]
+fn test_binary_is_base64_encoded() {
+    let path = get_test_file("keystore.der");
+    assert!(String::from_utf8(std::fs::read(&path).unwrap()).is_err());
+
+    let value = load_value(&path, &Options::new().binary(true)).unwrap();
+    assert!(value.into_string().unwrap() == "MIIA//6AQQo=");
+}
+
+#[test]
+fn test_binary_takes_precedence_over_raw() {
+    let options = Options::new().binary(true).raw(true);
+    let value = load_value(&get_test_file("password"), &options).unwrap();
+    assert!(value.into_string().unwrap() == "ICBwYXNzd29yZAo=");
+}
+
+#[test]
+fn test_binary_environment() {
+    temp_env::with_var(
+        "BIN_KEYSTORE_FILE",
+        Some(get_test_file("keystore.der")),
+        || {
+            let source =
+                EnvironmentSecretFile::with_prefix("BIN").options(Options::new().binary(true));
+            let map = source.collect().unwrap();
+            assert!(map["keystore"].clone().into_string().unwrap() == "MIIA//6AQQo=");
+        },
+    )
+}
+
+#[test]
+fn test_base64url_decoded() {
+    use config_secret::Base64Variant;
+
+    let options = Options::new()
+        .raw(true)
+        .base64(true)
+        .base64_variant(Base64Variant::UrlSafeNoPad);
+    let value = load_value(&get_test_file("client-secret.b64url"), &options).unwrap();
+    assert!(value.into_string().unwrap() == "client>secret?~");
+
+    let options = options.base64_variant(Base64Variant::UrlSafe);
+    let value = load_value(&get_test_file("subject.b64url"), &options).unwrap();
+    assert!(value.into_string().unwrap() == "sub?>~ject");
+}
+
+#[test]
+fn test_base64_wrong_variant() {
+    let options = Options::new().raw(true).base64(true);
+    let err = load_value(&get_test_file("client-secret.b64url"), &options).unwrap_err();
+
+    let message = err.to_string();
+    assert!(message.contains("not valid standard base64"));
+    assert!(message.contains("it is URL-safe base64"));
+    assert!(!message.contains("Y2xp"));
+}
@@ -0,0 +1,78 @@
+use config::{Config, ConfigError, Map, Value, ValueKind};
+use config_secret::{load_value, EnvironmentSecretFile, Options, SecretError, SecretFormat};
+
+mod helpers;
+use crate::helpers::get_test_file;
+
+/// Lines of `key = value` pairs.
+struct KeyValue;
+
+impl SecretFormat for KeyValue {
+    fn parse(&self, bytes: &[u8]) -> Result<Value, SecretError> {
+        let text = std::str::from_utf8(bytes)
+            .map_err(|_| ConfigError::Message("key-value secrets must be UTF-8".into()))?;
+
+        let mut map = Map::new();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| ConfigError::Message("expected `key = value`".into()))?;
+            map.insert(key.trim().to_string(), Value::from(value.trim()));
+        }
+
+        Ok(Value::new(None, ValueKind::Table(map)))
+    }
+}
+
+#[test]
+fn test_register_format() {
+    let options = Options::new().register_format("KV", Box::new(KeyValue));
+    let table = load_value(&get_test_file("db.kv"), &options)
+        .unwrap()
+        .into_table()
+        .unwrap();
+
+    assert!(table["host"].clone().into_string().unwrap() == "10.0.0.5");
+
+    assert!(load_value(&get_test_file("db.kv"), &Options::default()).is_err());
+}
+
+#[test]
+fn test_register_format_environment() {
+    temp_env::with_var("CF_DB_FILE", Some(get_test_file("db.kv")), || {
+        let source = EnvironmentSecretFile::with_prefix("CF")
+            .options(Options::new().register_format("kv", Box::new(KeyValue)));
+        let config = Config::builder().add_source(source).build().unwrap();
+
+        assert!(config.get::<u16>("db.port").unwrap() == 5432);
+    })
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_builtin_format_is_secret_format() {
+    let value = SecretFormat::parse(&config::FileFormat::Json, br#"{"port": 5000}"#).unwrap();
+    assert!(value.into_table().unwrap().contains_key("port"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_builtin_format_ignores_bom() {
+    let value =
+        SecretFormat::parse(&config::FileFormat::Json, b"\xef\xbb\xbf{\"port\": 5000}").unwrap();
+    assert!(value.into_table().unwrap().contains_key("port"));
+}
+
+#[cfg(feature = "binary")]
+#[test]
+fn test_register_format_base64() {
+    let options = Options::new()
+        .register_format("kv", Box::new(KeyValue))
+        .base64(true);
+    let table = load_value(&get_test_file("db-base64.kv"), &options)
+        .unwrap()
+        .into_table()
+        .unwrap();
+
+    assert!(table["port"].clone().into_string().unwrap() == "5432");
+}
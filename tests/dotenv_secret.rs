@@ -0,0 +1,43 @@
+use config::{Config, Source};
+use config_secret::DotenvSecret;
+
+mod helpers;
+use crate::helpers::get_test_file;
+
+#[test]
+fn test_dotenv_secret() {
+    let source = DotenvSecret::new(get_test_file("store.env"));
+    let map = source.collect().unwrap();
+
+    assert!(map.len() == 4);
+    assert!(map["app_db__password"].clone().into_string().unwrap() == "db-secret");
+}
+
+#[test]
+fn test_dotenv_secret_from_path() {
+    let source = DotenvSecret::from(get_test_file("store.env"));
+    assert!(source.collect().unwrap().len() == 4);
+
+    let source: DotenvSecret = "tests/assets/store.env".into();
+    assert!(source.collect().unwrap().len() == 4);
+}
+
+#[test]
+fn test_dotenv_secret_prefix_and_separator() {
+    let source = DotenvSecret::new(get_test_file("store.env"))
+        .prefix("APP")
+        .separator("__");
+
+    let config = Config::builder().add_source(source).build().unwrap();
+    assert!(config.get::<String>("db.password").unwrap() == "db-secret");
+    assert!(config.get::<String>("db.username").unwrap() == "admin");
+    assert!(config.get::<String>("api_token").unwrap() == "token");
+    assert!(config.get::<String>("other_token").is_err());
+}
+
+#[test]
+fn test_dotenv_secret_required() {
+    let source = DotenvSecret::new(get_test_file("not-available.env"));
+    assert!(source.collect().is_err());
+    assert!(source.required(false).collect().unwrap().is_empty());
+}
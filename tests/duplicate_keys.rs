@@ -0,0 +1,59 @@
+#![cfg(any(feature = "json", feature = "yaml"))]
+
+use config_secret::{load_value, DuplicatePolicy, Options, SecretError};
+
+mod helpers;
+use crate::helpers::get_test_file;
+
+fn load(name: &str, policy: DuplicatePolicy) -> Result<config::Value, SecretError> {
+    load_value(&get_test_file(name), &Options::new().duplicate_keys(policy))
+}
+
+fn get(value: config::Value, key: &str) -> String {
+    let table = value.into_table().unwrap();
+    table[key].clone().into_string().unwrap()
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_duplicate_keys_json() {
+    let err = load_value(&get_test_file("duplicate.json"), &Options::default()).unwrap_err();
+    assert!(matches!(err, SecretError::DuplicateKey { ref key, .. } if key == "admin_token"));
+
+    let value = load("duplicate.json", DuplicatePolicy::First).unwrap();
+    assert!(get(value, "admin_token") == "first");
+
+    let value = load("duplicate.json", DuplicatePolicy::Last).unwrap();
+    assert!(get(value, "admin_token") == "second");
+
+    assert!(load("config.json", DuplicatePolicy::Error).is_ok());
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_duplicate_keys_yaml() {
+    let err = load("duplicate.yaml", DuplicatePolicy::Error).unwrap_err();
+    assert!(err.to_string().contains("`host`"));
+    assert!(load("duplicate.yaml", DuplicatePolicy::First).is_err());
+
+    let value = load("duplicate.yaml", DuplicatePolicy::Last).unwrap();
+    let server = value.into_table().unwrap()["server"].clone();
+    assert!(get(server, "host") == "10.0.0.1");
+
+    assert!(load("config.yaml", DuplicatePolicy::Error).is_ok());
+    assert!(load("list.yaml", DuplicatePolicy::Error).is_ok());
+}
+
+#[cfg(all(feature = "json", feature = "yaml"))]
+#[test]
+fn test_duplicate_keys_format_chain() {
+    use config::FileFormat;
+
+    let options = Options::new().format_chain(vec![FileFormat::Json, FileFormat::Yaml]);
+    let err = load_value(&get_test_file("duplicate.json"), &options).unwrap_err();
+    assert!(matches!(err, SecretError::DuplicateKey { .. }));
+
+    let options = options.duplicate_keys(DuplicatePolicy::First);
+    let value = load_value(&get_test_file("duplicate.json"), &options).unwrap();
+    assert!(get(value, "admin_token") == "first");
+}
@@ -0,0 +1,31 @@
+#![cfg(all(feature = "encoding", feature = "json"))]
+
+use config_secret::{load_value, Encoding, Options};
+
+mod helpers;
+use crate::helpers::get_test_file;
+
+fn load_host(file: &str, encoding: Encoding) -> Result<String, config_secret::SecretError> {
+    let options = Options::new().encoding(encoding);
+    let table = load_value(&get_test_file(file), &options)?.into_table()?;
+    let server = table.get("server").unwrap().clone().into_table()?;
+    Ok(server.get("host").unwrap().clone().into_string()?)
+}
+
+#[test]
+fn test_utf16_encodings() {
+    assert!(load_host("utf16le.json", Encoding::Utf16Le).unwrap() == "0.0.0.0");
+    assert!(load_host("utf16be.json", Encoding::Utf16Be).unwrap() == "0.0.0.0");
+}
+
+#[test]
+fn test_auto_encoding() {
+    assert!(load_host("utf16le.json", Encoding::Auto).unwrap() == "0.0.0.0");
+    assert!(load_host("utf16be.json", Encoding::Auto).unwrap() == "0.0.0.0");
+    assert!(load_host("config.json", Encoding::Auto).unwrap() == "0.0.0.0");
+}
+
+#[test]
+fn test_utf8_rejects_utf16() {
+    assert!(load_host("utf16le.json", Encoding::Utf8).is_err());
+}
@@ -0,0 +1,94 @@
+use config::{Config, Source};
+use config_secret::FileSecret;
+
+mod helpers;
+use crate::helpers::{get_test_file, ScopedSettings};
+
+#[test]
+fn test_file_is_inserted_under_key() {
+    let source = FileSecret::new("a", get_test_file("config.json"));
+    assert!(source.collect().unwrap().contains_key("a"));
+}
+
+#[test]
+#[should_panic]
+fn test_nofile() {
+    let source = FileSecret::new("a", get_test_file("not-available-config.json"));
+    source.collect().unwrap();
+}
+
+#[test]
+fn test_scoped_serialize() {
+    let source = FileSecret::new("a", get_test_file("config.yaml"));
+
+    let config = Config::builder().add_source(source).build().unwrap();
+
+    let settings = config.try_deserialize::<ScopedSettings>().unwrap();
+
+    assert!(settings.a.server.host == "0.0.0.0");
+    assert!(settings.a.server.port == 5000);
+    assert!(
+        settings.a.redis.nodes
+            == vec![
+                "redis://10.0.0.1:6379",
+                "redis://10.0.0.2:6379",
+                "redis://10.0.0.3:6379"
+            ]
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_root_scalar() {
+    let source = FileSecret::new("redis.password", get_test_file("scalar.json"));
+
+    let config = Config::builder().add_source(source).build().unwrap();
+
+    assert!(config.get_string("redis.password").unwrap() == "just-a-secret");
+}
+
+#[test]
+fn test_candidates_first_existing() {
+    let source = FileSecret::with_candidates(
+        "a",
+        vec![
+            get_test_file("not-available-config.json"),
+            get_test_file("config.yaml"),
+            get_test_file("config.json"),
+        ],
+    );
+
+    let config = Config::builder().add_source(source).build().unwrap();
+    let settings = config.try_deserialize::<ScopedSettings>().unwrap();
+    assert!(settings.a.redis.nodes.len() == 3);
+}
+
+#[test]
+fn test_candidates_none_existing() {
+    let candidates = vec![
+        get_test_file("not-available-config.json"),
+        get_test_file("not-available-config.yaml"),
+    ];
+
+    let source = FileSecret::with_candidates("a", candidates.clone());
+    let message = source.collect().unwrap_err().to_string();
+    assert!(message.contains("not-available-config.json"));
+    assert!(message.contains("not-available-config.yaml"));
+
+    let source = FileSecret::with_candidates("a", candidates).required(false);
+    assert!(source.collect().unwrap().is_empty());
+}
+
+#[test]
+fn test_last_stats() {
+    let source = FileSecret::new("a", get_test_file("config.json"));
+    assert!(source.last_stats().files == 0);
+
+    source.collect().unwrap();
+    let size = std::fs::metadata(get_test_file("config.json"))
+        .unwrap()
+        .len();
+    let stats = source.last_stats();
+    assert!(stats.files == 1);
+    assert!(stats.bytes == size);
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "hcl")]
+
+use config::Config;
+use config_secret::{load_value, EnvironmentSecretFile, Options};
+
+mod helpers;
+use crate::helpers::{get_test_file, Settings};
+
+#[test]
+fn test_hcl_blocks() {
+    let value = load_value(&get_test_file("config.hcl"), &Options::default()).unwrap();
+    let settings = value.try_deserialize::<Settings>().unwrap();
+
+    assert!(settings.server.host == "0.0.0.0");
+    assert!(settings.server.port == 5000);
+    assert!(settings.redis.nodes.len() == 3);
+}
+
+#[test]
+fn test_hcl_full_configuration() {
+    temp_env::with_var("HCL_FILE", Some(get_test_file("config.hcl")), || {
+        let source = EnvironmentSecretFile::with_prefix("HCL");
+        let config = Config::builder().add_source(source).build().unwrap();
+        let settings = config.try_deserialize::<Settings>().unwrap();
+
+        assert!(settings.redis.nodes[2] == "redis://10.0.0.3:6379");
+    })
+}
+
+#[test]
+fn test_hcl_block_labels() {
+    let value = load_value(&get_test_file("labels.hcl"), &Options::default()).unwrap();
+    let table = value.into_table().unwrap();
+    let database = table["database"].clone().into_table().unwrap();
+    let primary = database["primary"].clone().into_table().unwrap();
+
+    assert!(primary["password"].clone().into_string().unwrap() == "secret");
+}
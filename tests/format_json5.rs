@@ -0,0 +1,37 @@
+#![cfg(feature = "json5")]
+
+use config::{Config, FileFormat};
+use config_secret::{load_value, EnvironmentSecretFile, Options};
+
+mod helpers;
+use crate::helpers::{get_test_file, Settings};
+
+#[test]
+fn test_json5_comments() {
+    let value = load_value(&get_test_file("config.json5"), &Options::default()).unwrap();
+    let settings = value.try_deserialize::<Settings>().unwrap();
+
+    assert!(settings.server.host == "0.0.0.0");
+    assert!(settings.server.port == 5000);
+    assert!(settings.redis.nodes.len() == 3);
+}
+
+#[test]
+fn test_json5_format_override() {
+    let options = Options::new().format(FileFormat::Json5);
+    let value = load_value(&get_test_file("server-json5"), &options).unwrap();
+    let settings = value.try_deserialize::<Settings>().unwrap();
+
+    assert!(settings.server.port == 5000);
+}
+
+#[test]
+fn test_json5_full_configuration() {
+    temp_env::with_var("J5_FILE", Some(get_test_file("config.json5")), || {
+        let source = EnvironmentSecretFile::with_prefix("J5");
+        let config = Config::builder().add_source(source).build().unwrap();
+        let settings = config.try_deserialize::<Settings>().unwrap();
+
+        assert!(settings.redis.nodes[0] == "redis://10.0.0.1:6379");
+    })
+}
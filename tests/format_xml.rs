@@ -0,0 +1,39 @@
+#![cfg(feature = "xml")]
+
+use config::Config;
+use config_secret::{load_value, EnvironmentSecretFile, Options};
+
+mod helpers;
+use crate::helpers::{get_test_file, ScopedSettings, Settings};
+
+#[test]
+fn test_xml_convention() {
+    let value = load_value(&get_test_file("config.xml"), &Options::default()).unwrap();
+    let settings = value.try_deserialize::<Settings>().unwrap();
+
+    assert!(settings.server.host == "0.0.0.0");
+    assert!(settings.server.port == 5000);
+    assert!(
+        settings.redis.nodes
+            == vec![
+                "redis://10.0.0.1:6379",
+                "redis://10.0.0.2:6379",
+                "redis://10.0.0.3:6379"
+            ]
+    );
+}
+
+#[test]
+fn test_scoped_serialize_xml() {
+    temp_env::with_var("X_A_FILE", Some(get_test_file("config.xml")), || {
+        let source = EnvironmentSecretFile::with_prefix("X").separator("_");
+
+        let config = Config::builder().add_source(source).build().unwrap();
+
+        let settings = config.try_deserialize::<ScopedSettings>().unwrap();
+
+        assert!(settings.a.server.host == "0.0.0.0");
+        assert!(settings.a.server.port == 5000);
+        assert!(settings.a.redis.nodes.len() == 3);
+    })
+}
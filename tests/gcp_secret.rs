@@ -0,0 +1,84 @@
+#![cfg(feature = "gcp")]
+
+use config::{Config, Source};
+use config_secret::GcpSecretManagerSource;
+
+mod helpers;
+use crate::helpers::{http::serve, Settings};
+
+/// Returns the response of the `access` method for `payload`.
+fn access_response(payload: &str) -> String {
+    format!(
+        r#"{{"name": "version", "payload": {{"data": "{}"}}}}"#,
+        payload
+    )
+}
+
+#[test]
+fn test_json_payload() {
+    // {"server": {"host": "0.0.0.0", "port": 5000}, "redis": {"nodes": ["redis://10.0.0.1:6379"]}}
+    let payload = "eyJzZXJ2ZXIiOiB7Imhvc3QiOiAiMC4wLjAuMCIsICJwb3J0IjogNTAwMH0sICJyZWRpcyI6IHsibm9kZXMiOiBbInJlZGlzOi8vMTAuMC4wLjE6NjM3OSJdfX0=";
+    let (url, requests) = serve(vec![(200, access_response(payload))]);
+
+    let source = GcpSecretManagerSource::new("app", "projects/p/secrets/app")
+        .endpoint(&url)
+        .access_token("token");
+    let config = Config::builder().add_source(source).build().unwrap();
+    let settings = config.get::<Settings>("app").unwrap();
+
+    assert!(settings.server.port == 5000);
+    assert!(
+        requests.recv().unwrap()
+            == "GET /v1/projects/p/secrets/app/versions/latest:access HTTP/1.1"
+    );
+}
+
+#[test]
+fn test_raw_payload() {
+    // hunter2
+    let (url, _) = serve(vec![(200, access_response("aHVudGVyMg=="))]);
+
+    let source =
+        GcpSecretManagerSource::new("redis.password", "projects/p/secrets/redis/versions/3")
+            .endpoint(&url)
+            .access_token("token");
+    let map = source.collect().unwrap();
+
+    assert!(map["redis.password"].clone().into_string().unwrap() == "hunter2");
+}
+
+#[test]
+fn test_missing_version() {
+    let not_found = r#"{"error": {"code": 404, "status": "NOT_FOUND"}}"#.to_string();
+    let (url, _) = serve(vec![(404, not_found.clone()), (404, not_found)]);
+
+    let source = GcpSecretManagerSource::new("app", "projects/p/secrets/missing")
+        .endpoint(&url)
+        .access_token("token");
+    assert!(source.collect().is_err());
+    assert!(source.required(false).collect().unwrap().is_empty());
+}
+
+#[test]
+fn test_api_error() {
+    let denied = r#"{"error": {"code": 403, "status": "PERMISSION_DENIED"}}"#.to_string();
+    let (url, _) = serve(vec![(403, denied)]);
+
+    let source = GcpSecretManagerSource::new("app", "projects/p/secrets/app")
+        .endpoint(&url)
+        .access_token("token");
+    let message = source.collect().unwrap_err().to_string();
+
+    assert!(message.contains("projects/p/secrets/app/versions/latest"));
+    assert!(message.contains("PERMISSION_DENIED"));
+}
+
+#[test]
+fn test_debug_redacts_token() {
+    let source =
+        GcpSecretManagerSource::new("app", "projects/p/secrets/s").access_token("ya29.secret");
+    let debug = format!("{:?}", source);
+
+    assert!(debug.contains("access_token: Some(\"<redacted>\")"));
+    assert!(!debug.contains("ya29.secret"));
+}
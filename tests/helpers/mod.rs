@@ -1,3 +1,6 @@
+#![allow(dead_code, unused_imports)]
+
+pub mod http;
 mod settings;
 use std::path::PathBuf;
 
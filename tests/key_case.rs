@@ -0,0 +1,41 @@
+#![cfg(feature = "case")]
+
+use config_secret::{EnvironmentSecretFile, KeyCase, MapVarProvider};
+
+mod helpers;
+use crate::helpers::get_test_file;
+
+fn keys(case: KeyCase) -> Vec<String> {
+    let path = get_test_file("config.json");
+    let vars = MapVarProvider::new()
+        .with_var("KC_DB_HOST_FILE", path.to_str().unwrap())
+        .with_var("KC_REDIS__NODE_LIST_FILE", path.to_str().unwrap());
+
+    EnvironmentSecretFile::with_prefix("KC")
+        .prefix_separator("_")
+        .separator("__")
+        .suffix_separator("_")
+        .var_provider(vars)
+        .key_case(case)
+        .keys()
+}
+
+#[test]
+fn test_key_case_kebab() {
+    assert!(keys(KeyCase::Kebab) == vec!["db-host", "redis.node-list"]);
+}
+
+#[test]
+fn test_key_case_camel() {
+    assert!(keys(KeyCase::Camel) == vec!["dbHost", "redis.nodeList"]);
+}
+
+#[test]
+fn test_key_case_pascal() {
+    assert!(keys(KeyCase::Pascal) == vec!["DbHost", "Redis.NodeList"]);
+}
+
+#[test]
+fn test_key_case_snake() {
+    assert!(keys(KeyCase::Snake) == vec!["db_host", "redis.node_list"]);
+}
@@ -0,0 +1,26 @@
+#![cfg(feature = "keyring")]
+
+use config::Source;
+use config_secret::KeyringSecret;
+
+fn use_mock_store() {
+    keyring::set_default_credential_builder(keyring::mock::default_credential_builder());
+}
+
+#[test]
+fn test_keyring_missing_required() {
+    use_mock_store();
+
+    let source = KeyringSecret::new("redis.password", "config-secret-test", "missing");
+    let message = source.collect().unwrap_err().to_string();
+    assert!(message.contains("config-secret-test"));
+}
+
+#[test]
+fn test_keyring_missing_optional() {
+    use_mock_store();
+
+    let source =
+        KeyringSecret::new("redis.password", "config-secret-test", "missing").required(false);
+    assert!(source.collect().unwrap().is_empty());
+}
@@ -0,0 +1,57 @@
+use std::{io, path::Path, thread, time::Duration};
+
+use config::Source;
+use config_secret::{EnvironmentSecretFile, FileReader, MapVarProvider, Options};
+
+mod helpers;
+
+/// Reader of a mount that hangs.
+#[derive(Debug)]
+struct SlowReader;
+
+impl FileReader for SlowReader {
+    fn read(&self, _: &Path) -> io::Result<Vec<u8>> {
+        thread::sleep(Duration::from_secs(2));
+        Ok(b"{}".to_vec())
+    }
+
+    fn exists(&self, _: &Path) -> bool {
+        true
+    }
+}
+
+fn slow_source(prefix: &str) -> EnvironmentSecretFile {
+    let vars = MapVarProvider::new().with_var(&format!("{}_A_FILE", prefix), "/mnt/nfs/a.json");
+    let options = Options::new()
+        .reader(SlowReader)
+        .read_timeout(Duration::from_millis(50));
+
+    EnvironmentSecretFile::with_prefix(prefix)
+        .var_provider(vars)
+        .options(options)
+}
+
+#[test]
+fn test_read_timeout_required() {
+    let err = slow_source("TR").collect().unwrap_err();
+    assert!(err.to_string().contains("/mnt/nfs/a.json"));
+}
+
+#[test]
+fn test_read_timeout_optional() {
+    let source = slow_source("TO").required(false);
+    assert!(source.collect().unwrap().is_empty());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_read_timeout_in_time() {
+    let path = helpers::get_test_file("config.json");
+    let vars = MapVarProvider::new().with_var("TT_A_FILE", path.to_str().unwrap());
+    let options = Options::new().read_timeout(Duration::from_secs(10));
+
+    let source = EnvironmentSecretFile::with_prefix("TT")
+        .var_provider(vars)
+        .options(options);
+    assert!(source.collect().unwrap().contains_key("a"));
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "json")]
+
+use config_secret::{load_value, Options, SecretError, UnresolvedPolicy};
+
+mod helpers;
+use crate::helpers::get_test_file;
+
+fn get(value: &config::Value, key: &str) -> String {
+    let table = value.clone().into_table().unwrap();
+    let (section, key) = key.split_once('.').unwrap();
+    let section = table[section].clone().into_table().unwrap();
+    section[key].clone().into_string().unwrap()
+}
+
+#[test]
+fn test_render_env() {
+    temp_env::with_vars(
+        vec![
+            ("RE_HOST", Some("10.0.0.1")),
+            ("RE_PASSWORD", Some("s3cret")),
+        ],
+        || {
+            let options = Options::new().render_env(true);
+            let value = load_value(&get_test_file("template.json"), &options).unwrap();
+            assert!(get(&value, "server.host") == "10.0.0.1");
+            assert!(get(&value, "server.port") == "5000");
+
+            assert!(get(&value, "redis.password") == "s3cret");
+
+            let options = Options::new();
+            let value = load_value(&get_test_file("template.json"), &options).unwrap();
+            assert!(get(&value, "server.host") == "${RE_HOST}");
+        },
+    )
+}
+
+#[test]
+fn test_render_env_unresolved() {
+    temp_env::with_vars(
+        vec![("RE_HOST", Some("10.0.0.1")), ("RE_PASSWORD", None)],
+        || {
+            let path = get_test_file("template.json");
+
+            let options = Options::new().render_env(true);
+            let err = load_value(&path, &options).unwrap_err();
+            assert!(
+                matches!(err, SecretError::UnresolvedPlaceholder { ref var, .. } if var == "RE_PASSWORD")
+            );
+
+            let options = options.unresolved_placeholders(UnresolvedPolicy::Keep);
+            let value = load_value(&path, &options).unwrap();
+            assert!(get(&value, "server.host") == "10.0.0.1");
+            assert!(get(&value, "redis.password") == "${RE_PASSWORD}");
+        },
+    )
+}
+
+#[test]
+fn test_render_env_var_provider() {
+    use config::{Config, Source};
+    use config_secret::{EnvironmentSecretFile, MapVarProvider};
+
+    let template = get_test_file("template.json");
+    let vars = MapVarProvider::new()
+        .with_var("RP_APP_FILE", template.to_str().unwrap())
+        .with_var("RE_HOST", "10.0.0.2")
+        .with_var("RE_PASSWORD", "from-provider");
+
+    temp_env::with_vars(
+        vec![("RE_HOST", Some("10.0.0.1")), ("RE_PASSWORD", None)],
+        || {
+            let source = EnvironmentSecretFile::with_prefix("RP")
+                .options(Options::new().render_env(true))
+                .var_provider(vars.clone());
+            let app = source.collect().unwrap().remove("app").unwrap();
+            assert!(get(&app, "server.host") == "10.0.0.2");
+            assert!(get(&app, "redis.password") == "from-provider");
+
+            // The provider is kept when the options are set afterwards
+            let source = EnvironmentSecretFile::with_prefix("RP")
+                .var_provider(vars.clone())
+                .options(Options::new().render_env(true));
+            let config = Config::builder().add_source(source).build().unwrap();
+            assert!(config.get::<String>("app.server.host").unwrap() == "10.0.0.2");
+        },
+    )
+}
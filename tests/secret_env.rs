@@ -1,5 +1,5 @@
 use config::{Config, Source};
-use config_secret::EnvironmentSecretFile;
+use config_secret::{EnvironmentSecretFile, Options, Position};
 
 mod helpers;
 use crate::helpers::{get_test_file, ScopedSettings, Settings};
@@ -49,6 +49,30 @@ fn test_separator_behavior() {
     })
 }
 
+#[test]
+fn test_key_delimiter_behavior() {
+    temp_env::with_var("KD_B_A_FILE", Some(get_test_file("config.json")), || {
+        let source = EnvironmentSecretFile::with_prefix("KD")
+            .separator("_")
+            .key_delimiter("__");
+        assert!(source.collect().unwrap().contains_key("b__a"));
+    })
+}
+
+#[test]
+fn test_key_delimiter_aligned_with_config() {
+    temp_env::with_var("KDC_A_FILE", Some(get_test_file("config.json")), || {
+        let source = EnvironmentSecretFile::with_prefix("KDC")
+            .separator("_")
+            .key_delimiter(".");
+
+        let config = Config::builder().add_source(source).build().unwrap();
+        let settings = config.try_deserialize::<ScopedSettings>().unwrap();
+
+        assert!(settings.a.server.port == 5000);
+    })
+}
+
 #[test]
 fn test_empty_value_is_ignored() {
     temp_env::with_var("C_A_B_FILE", Some(""), || {
@@ -57,6 +81,17 @@ fn test_empty_value_is_ignored() {
     })
 }
 
+#[test]
+fn test_whitespace_value_is_ignored() {
+    temp_env::with_var("C_W_B_FILE", Some("   "), || {
+        let source = EnvironmentSecretFile::with_prefix("c");
+        assert!(source.collect().is_err());
+
+        let source = EnvironmentSecretFile::with_prefix("c").trim_value_check(true);
+        assert!(!source.collect().unwrap().contains_key("w_b"));
+    })
+}
+
 #[test]
 fn test_keep_prefix() {
     temp_env::with_var("C_A_C_FILE", Some(get_test_file("config.json")), || {
@@ -73,6 +108,22 @@ fn test_keep_prefix() {
     })
 }
 
+#[test]
+fn test_keep_prefix_case() {
+    temp_env::with_var("MixD_A_FILE", Some(get_test_file("config.json")), || {
+        let source = EnvironmentSecretFile::with_prefix("mixd")
+            .separator("_")
+            .keep_prefix(true);
+        assert!(source.collect().unwrap().contains_key("mixd.a"));
+
+        let source = EnvironmentSecretFile::with_prefix("mixd")
+            .separator("_")
+            .keep_prefix(true)
+            .keep_prefix_case(true);
+        assert!(source.collect().unwrap().contains_key("MixD.a"));
+    })
+}
+
 #[test]
 fn test_custom_separator_behavior() {
     temp_env::with_var("C.B.A.FILE", Some(get_test_file("config.json")), || {
@@ -179,6 +230,108 @@ fn test_scoped_serialize() {
     })
 }
 
+/// Source reading one scalar per variable, such as `A_SERVER_HOST_FILE`, isolated from the
+/// process environment
+fn scalar_per_variable() -> EnvironmentSecretFile {
+    use config_secret::MapVarProvider;
+
+    let leaf = |name: &str| get_test_file("leaves.d").join(name).display().to_string();
+    let vars = MapVarProvider::new()
+        .with_var("A_SERVER_HOST_FILE", &leaf("host"))
+        .with_var("A_SERVER_PORT_FILE", &leaf("port"));
+
+    EnvironmentSecretFile::default()
+        .separator("_")
+        .var_provider(vars)
+        .options(Options::new().raw(true))
+}
+
+#[test]
+fn test_scalar_per_variable() {
+    let source = scalar_per_variable();
+    assert!(source.keys() == vec!["a.server.host", "a.server.port"]);
+
+    let map = source.collect().unwrap();
+    assert!(map["a.server.host"].clone().into_string().unwrap() == "0.0.0.0");
+    assert!(map["a.server.port"].clone().into_int().unwrap() == 5000);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_scalar_per_variable_serialize() {
+    let nodes = config_secret::FileSecret::new("a.redis.nodes", get_test_file("list.json"));
+    let config = Config::builder()
+        .add_source(scalar_per_variable())
+        .add_source(nodes)
+        .build()
+        .unwrap();
+
+    let settings = config.try_deserialize::<ScopedSettings>().unwrap();
+    assert!(settings.a.server.host == "0.0.0.0");
+    assert!(settings.a.server.port == 5000);
+    assert!(settings.a.redis.nodes.len() == 3);
+}
+
+#[test]
+fn test_sources_precedence() {
+    temp_env::with_vars(
+        vec![
+            ("PB_A_FILE", Some(get_test_file("config.json"))),
+            ("PE_A_FILE", Some(get_test_file("override.json"))),
+            ("PN_A__SERVER_FILE", Some(get_test_file("port.json"))),
+        ],
+        || {
+            let source = |prefix| {
+                EnvironmentSecretFile::with_prefix(prefix)
+                    .separator("__")
+                    .prefix_separator("_")
+                    .suffix_separator("_")
+            };
+
+            // The later source wins at the leaf level, tables being merged
+            let config = Config::builder()
+                .add_source(source("PB"))
+                .add_source(source("PE"))
+                .build()
+                .unwrap();
+            let settings = config.try_deserialize::<ScopedSettings>().unwrap();
+            assert!(settings.a.server.port == 6000);
+            assert!(settings.a.server.host == "0.0.0.0");
+            assert!(settings.a.redis.nodes.len() == 3);
+
+            let config = Config::builder()
+                .add_source(source("PE"))
+                .add_source(source("PB"))
+                .build()
+                .unwrap();
+            assert!(config.get::<u16>("a.server.port").unwrap() == 5000);
+
+            // A nested key merges into the table of an earlier source
+            let config = Config::builder()
+                .add_source(source("PB"))
+                .add_source(source("PE"))
+                .add_source(source("PN"))
+                .build()
+                .unwrap();
+            let settings = config.try_deserialize::<ScopedSettings>().unwrap();
+            assert!(settings.a.server.port == 7000);
+            assert!(settings.a.server.host == "0.0.0.0");
+        },
+    )
+}
+
+#[test]
+fn test_explicit_scope() {
+    temp_env::with_var("SC_A_FILE", Some(get_test_file("config.json")), || {
+        let source = EnvironmentSecretFile::with_prefix("SC").scope("services.auth");
+        assert!(source.keys() == vec!["services.auth.a"]);
+
+        let config = Config::builder().add_source(source).build().unwrap();
+        let settings = config.get::<ScopedSettings>("services.auth").unwrap();
+        assert!(settings.a.server.port == 5000);
+    })
+}
+
 #[test]
 fn test_scoped_serialize_yaml() {
     temp_env::with_var("Y_A_FILE", Some(get_test_file("config.yaml")), || {
@@ -244,3 +397,1498 @@ fn test_full_serialize_yaml() {
         );
     })
 }
+
+#[test]
+fn test_post_process() {
+    temp_env::with_var("PP_A_FILE", Some(get_test_file("config.json")), || {
+        let source = EnvironmentSecretFile::with_prefix("PP").post_process(|m| {
+            if let Some(value) = m.remove("a") {
+                m.insert("renamed".to_string(), value);
+            }
+        });
+
+        let map = source.collect().unwrap();
+        assert!(!map.contains_key("a"));
+        assert!(map.contains_key("renamed"));
+    })
+}
+
+#[test]
+fn test_var_filter() {
+    temp_env::with_vars(
+        vec![
+            ("VF_A_FILE", Some(get_test_file("config.json"))),
+            ("VF_LEGACY_FILE", Some(get_test_file("config.json"))),
+        ],
+        || {
+            let source = EnvironmentSecretFile::with_prefix("VF")
+                .var_filter(|name| !name.contains("LEGACY"));
+
+            let map = source.collect().unwrap();
+            assert!(map.contains_key("a"));
+            assert!(!map.contains_key("legacy"));
+        },
+    )
+}
+
+#[test]
+fn test_resolver() {
+    use config::Value;
+    use config_secret::{MapVarProvider, SecretError};
+
+    let vars = MapVarProvider::new()
+        .with_var("RS_TOKEN_FILE", "/nonexistent/token")
+        .with_var("RS_A_FILE", get_test_file("config.json").to_str().unwrap())
+        .with_var("RS_BROKEN_FILE", "/nonexistent/broken");
+    let source = EnvironmentSecretFile::with_prefix("RS")
+        .resolver(|key| match key {
+            "token" => Ok(Some(Value::from("from-vault"))),
+            "broken" => Err(SecretError::Remote {
+                resource: key.into(),
+                reason: "denied".into(),
+            }),
+            _ => Ok(None),
+        })
+        .var_provider(vars);
+
+    let err = source.collect().unwrap_err();
+    assert!(err.to_string().contains("denied"));
+
+    let vars = MapVarProvider::new()
+        .with_var("RS_TOKEN_FILE", "/nonexistent/token")
+        .with_var("RS_A_FILE", get_test_file("config.json").to_str().unwrap());
+    let map = source.var_provider(vars).collect().unwrap();
+    assert!(map["token"].clone().into_string().unwrap() == "from-vault");
+
+    let settings = map["a"].clone().try_deserialize::<Settings>().unwrap();
+    assert!(settings.server.port == 5000);
+}
+
+#[test]
+fn test_dual_keys() {
+    use config_secret::MapVarProvider;
+
+    let host = get_test_file("password");
+    let vars = MapVarProvider::new()
+        .with_var("DK_SERVER_HOST_FILE", host.to_str().unwrap())
+        .with_var("DK_TOKEN_FILE", host.to_str().unwrap());
+    let source = EnvironmentSecretFile::with_prefix("DK")
+        .separator("_")
+        .options(Options::new().raw(true))
+        .dual_keys(true)
+        .var_provider(vars);
+    let config = Config::builder().add_source(source).build().unwrap();
+
+    let nested = config.get::<String>("server.host").unwrap();
+    assert!(nested == config.get::<String>("server_host").unwrap());
+    assert!(config.get::<String>("token").is_ok());
+}
+
+#[test]
+fn test_separators() {
+    use config_secret::MapVarProvider;
+
+    let path = get_test_file("password");
+    let vars = MapVarProvider::new()
+        .with_var("MYAPP_DB_HOST_FILE", path.to_str().unwrap())
+        .with_var("MYAPP-DB-PORT-FILE", path.to_str().unwrap())
+        .with_var("MYAPP-DB_USER-FILE", path.to_str().unwrap())
+        .with_var("MYAPP.DB.NAME.FILE", path.to_str().unwrap());
+    let source = EnvironmentSecretFile::with_prefix("MYAPP")
+        .options(Options::new().raw(true))
+        .separators(vec!["_", "-"])
+        .var_provider(vars);
+    let map = source.collect().unwrap();
+
+    assert!(map.len() == 3);
+    assert!(map.contains_key("db.host"));
+    assert!(map.contains_key("db.port"));
+    assert!(map.contains_key("db.user"));
+}
+
+#[test]
+fn test_extensionless_file_without_format() {
+    use config_secret::{MapVarProvider, SecretError};
+
+    let path = get_test_file("dsn");
+    let vars = MapVarProvider::new().with_var("NX_DSN_FILE", path.to_str().unwrap());
+    let source = EnvironmentSecretFile::with_prefix("NX").var_provider(vars);
+
+    let err = source.check().unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("NX_DSN_FILE"));
+    assert!(message.contains(path.to_str().unwrap()));
+    assert!(message.contains("format(...)"));
+    assert!(message.contains("raw(true)"));
+
+    match err {
+        SecretError::Var { var, source, .. } => {
+            assert!(var == "NX_DSN_FILE");
+            assert!(matches!(
+                *source,
+                SecretError::UnknownFormat {
+                    extension: None,
+                    ..
+                }
+            ));
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn test_validate() {
+    assert!(EnvironmentSecretFile::with_prefix("A").validate().is_ok());
+    assert!(EnvironmentSecretFile::default().validate().is_ok());
+
+    assert!(EnvironmentSecretFile::with_prefix("").validate().is_err());
+    assert!(EnvironmentSecretFile::default()
+        .suffix("")
+        .validate()
+        .is_err());
+    assert!(EnvironmentSecretFile::with_prefix("A")
+        .suffix("")
+        .suffix_separator("-")
+        .validate()
+        .is_err());
+    assert!(EnvironmentSecretFile::default()
+        .prefix_separator("-")
+        .validate()
+        .is_err());
+    assert!(EnvironmentSecretFile::default()
+        .keep_prefix(true)
+        .validate()
+        .is_err());
+    assert!(EnvironmentSecretFile::with_prefix("A")
+        .separator("")
+        .validate()
+        .is_err());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_merge_inline() {
+    temp_env::with_vars(
+        vec![
+            (
+                "MI_A_FILE",
+                Some(get_test_file("config.json").into_os_string()),
+            ),
+            ("MI_A", Some(r#"{"server": {"port": 6000}}"#.into())),
+        ],
+        || {
+            let source = EnvironmentSecretFile::with_prefix("MI").separator("_");
+            let config = Config::builder().add_source(source).build().unwrap();
+            let settings = config.try_deserialize::<ScopedSettings>().unwrap();
+            assert!(settings.a.server.port == 5000);
+
+            let source = EnvironmentSecretFile::with_prefix("MI")
+                .separator("_")
+                .merge_inline(true);
+            let config = Config::builder().add_source(source).build().unwrap();
+            let settings = config.try_deserialize::<ScopedSettings>().unwrap();
+            assert!(settings.a.server.host == "0.0.0.0");
+            assert!(settings.a.server.port == 6000);
+            assert!(settings.a.redis.nodes.len() == 3);
+        },
+    )
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_merge_inline_unset_and_invalid() {
+    let config = get_test_file("config.json");
+
+    temp_env::with_vars(
+        vec![
+            ("MU_A_FILE", Some(config.clone().into_os_string())),
+            ("MU_A", Some("   ".into())),
+        ],
+        || {
+            let source = EnvironmentSecretFile::with_prefix("MU")
+                .merge_inline(true)
+                .trim_value_check(true);
+            let config = Config::builder().add_source(source).build().unwrap();
+            let settings = config.try_deserialize::<ScopedSettings>().unwrap();
+            assert!(settings.a.server.port == 5000);
+        },
+    );
+
+    temp_env::with_vars(
+        vec![
+            ("MU_A_FILE", Some(config.into_os_string())),
+            ("MU_A", Some("{not json".into())),
+        ],
+        || {
+            let source = EnvironmentSecretFile::with_prefix("MU").merge_inline(true);
+            let message = source.collect().unwrap_err().to_string();
+            assert!(message.contains("inline content of environment variable MU_A:"));
+        },
+    );
+}
+
+#[test]
+fn test_merge_inline_marker_combinations() {
+    use config_secret::{MapVarProvider, Position, SuffixRule};
+
+    let password = get_test_file("password");
+    let password = password.to_str().unwrap();
+    let dsn = get_test_file("dsn");
+    let dsn = dsn.to_str().unwrap();
+    for position in [Position::Suffix, Position::Prefix] {
+        for rule in [SuffixRule::Always, SuffixRule::WithoutPrefix] {
+            for merge in [false, true] {
+                let (db, kv) = match position {
+                    Position::Suffix => ("APP_DB_FILE", "KV_FILE"),
+                    Position::Prefix => ("APP_FILE_DB", "FILE_KV"),
+                };
+                let mut vars = MapVarProvider::new()
+                    .with_var(db, password)
+                    .with_var(kv, password)
+                    .with_var("APP_DBPASSWORD", password)
+                    .with_var("APP_DBPAS", dsn)
+                    .with_var("APP_SWORD", dsn);
+                if rule == SuffixRule::Always {
+                    vars = vars.with_var("APP_DB", "inline");
+                }
+                let source = EnvironmentSecretFile::with_prefix("APP")
+                    .marker_position(position)
+                    .suffix_required_when(rule)
+                    .merge_inline(merge)
+                    .options(Options::new().raw(true))
+                    .var_provider(vars);
+
+                let case = format!("{:?} {:?} {}", position, rule, merge);
+                let map = source.collect().unwrap();
+                let db = map["db"].clone().into_string().unwrap();
+                if merge && rule == SuffixRule::Always {
+                    assert!(db == "inline", "{}", case);
+                } else {
+                    assert!(db == "  password", "{}", case);
+                }
+                let without_prefix = rule == SuffixRule::WithoutPrefix;
+                assert!(map.contains_key("kv") == without_prefix, "{}", case);
+                assert!(map.contains_key("dbpassword") == without_prefix, "{}", case);
+                if without_prefix {
+                    let value = map["dbpassword"].clone().into_string().unwrap();
+                    assert!(value == "  password", "{}", case);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_root_array_serialize() {
+    temp_env::with_var("RA_NODES_FILE", Some(get_test_file("list.json")), || {
+        let source = EnvironmentSecretFile::with_prefix("RA").separator("_");
+
+        let config = Config::builder().add_source(source).build().unwrap();
+
+        let settings = config.try_deserialize::<helpers::RedisSettings>().unwrap();
+
+        assert!(
+            settings.nodes
+                == vec![
+                    "redis://10.0.0.1:6379",
+                    "redis://10.0.0.2:6379",
+                    "redis://10.0.0.3:6379"
+                ]
+        );
+    })
+}
+
+#[test]
+fn test_indirection_prefix() {
+    temp_env::with_vars(
+        vec![
+            ("IR_A_FILE", Some("__ref:IR_A_FILE_REAL".into())),
+            (
+                "IR_A_FILE_REAL",
+                Some(get_test_file("config.json").into_os_string()),
+            ),
+        ],
+        || {
+            let source = EnvironmentSecretFile::with_prefix("IR").indirection_prefix("__ref:");
+            assert!(source.collect().unwrap().contains_key("a"));
+
+            let source = EnvironmentSecretFile::with_prefix("IR");
+            assert!(source.collect().is_err());
+        },
+    )
+}
+
+#[test]
+fn test_indirection_missing_target() {
+    temp_env::with_var("IM_A_FILE", Some("__ref:IM_A_FILE_MISSING"), || {
+        let source = EnvironmentSecretFile::with_prefix("IM").indirection_prefix("__ref:");
+        let err = source.collect().unwrap_err();
+        assert!(err.to_string().contains("IM_A_FILE_MISSING"));
+    })
+}
+
+#[test]
+fn test_indirection_chain() {
+    temp_env::with_vars(
+        vec![
+            ("IC_A_FILE", Some("__ref:IC_A_FIRST".into())),
+            ("IC_A_FIRST", Some("__ref:IC_A_SECOND".into())),
+            (
+                "IC_A_SECOND",
+                Some(get_test_file("config.json").into_os_string()),
+            ),
+        ],
+        || {
+            let source = EnvironmentSecretFile::with_prefix("IC").indirection_prefix("__ref:");
+            assert!(source.collect().unwrap().contains_key("a"));
+        },
+    )
+}
+
+#[test]
+fn test_indirection_cycle() {
+    temp_env::with_vars(
+        vec![
+            ("CY_A_FILE", Some("__ref:CY_B_FILE")),
+            ("CY_B_FILE", Some("__ref:CY_A_FILE")),
+        ],
+        || {
+            let source = EnvironmentSecretFile::with_prefix("CY").indirection_prefix("__ref:");
+            let message = source.collect().unwrap_err().to_string();
+            assert!(
+                message.contains("CY_A_FILE -> CY_B_FILE -> CY_A_FILE")
+                    || message.contains("CY_B_FILE -> CY_A_FILE -> CY_B_FILE")
+            );
+        },
+    )
+}
+
+#[test]
+fn test_root_error_names_var() {
+    temp_env::with_var("RE_FILE", Some(get_test_file("broken.json")), || {
+        let err = EnvironmentSecretFile::with_prefix("RE")
+            .collect()
+            .unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("RE_FILE"));
+        assert!(message.contains("broken.json"));
+    })
+}
+
+#[test]
+fn test_key_from_field() {
+    temp_env::with_var("KF_FILE", Some(get_test_file("named.json")), || {
+        let source = EnvironmentSecretFile::with_prefix("KF").key_from_field("name");
+        let config = Config::builder().add_source(source).build().unwrap();
+
+        assert!(config.get::<String>("db-primary.host").unwrap() == "10.0.0.5");
+        assert!(config.get::<u16>("db-primary.port").unwrap() == 5432);
+        assert!(config.get::<String>("host").is_err());
+    })
+}
+
+#[test]
+fn test_key_from_missing_field() {
+    temp_env::with_var("KM_FILE", Some(get_test_file("config.json")), || {
+        let err = EnvironmentSecretFile::with_prefix("KM")
+            .key_from_field("name")
+            .collect()
+            .unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("KM_FILE"));
+        assert!(message.contains("`name`"));
+    })
+}
+
+#[test]
+fn test_key_error_names_var() {
+    temp_env::with_var("KE_A_FILE", Some(get_test_file("broken.json")), || {
+        let err = EnvironmentSecretFile::with_prefix("KE")
+            .collect()
+            .unwrap_err();
+        assert!(err.to_string().contains("KE_A_FILE"));
+    })
+}
+
+#[test]
+fn test_cache() {
+    let source = EnvironmentSecretFile::with_prefix("CA").cache(true);
+
+    temp_env::with_var("CA_A_FILE", Some(get_test_file("config.json")), || {
+        assert!(source.collect().unwrap().contains_key("a"));
+    });
+
+    temp_env::with_var("CA_B_FILE", Some(get_test_file("config.json")), || {
+        let map = source.collect().unwrap();
+        assert!(map.contains_key("a"));
+        assert!(!map.contains_key("b"));
+
+        source.invalidate();
+
+        let map = source.collect().unwrap();
+        assert!(!map.contains_key("a"));
+        assert!(map.contains_key("b"));
+    });
+}
+
+#[test]
+fn test_cache_debug_hides_secrets() {
+    let source = EnvironmentSecretFile::with_prefix("CD").cache(true);
+
+    temp_env::with_var("CD_A_FILE", Some(get_test_file("config.json")), || {
+        source.collect().unwrap();
+    });
+
+    let debug = format!("{:?}", source);
+    assert!(debug.contains("Cache { cached: true }"));
+    assert!(!debug.contains("0.0.0.0"));
+}
+
+#[test]
+fn test_disabled_suffix() {
+    temp_env::with_var("NS_A", Some(get_test_file("config.json")), || {
+        let source = EnvironmentSecretFile::with_prefix("NS").suffix("");
+        assert!(source.validate().is_ok());
+        assert!(source.collect().unwrap().contains_key("a"));
+    })
+}
+
+#[test]
+fn test_match_all_is_required() {
+    let source = EnvironmentSecretFile::default().suffix("");
+    assert!(source.validate().is_err());
+    assert!(source.collect().is_err());
+
+    let source = EnvironmentSecretFile::default().suffix("").match_all(true);
+    assert!(source.validate().is_ok());
+}
+
+#[test]
+fn test_audit() {
+    temp_env::with_vars(
+        vec![
+            (
+                "AU_A_FILE",
+                Some(get_test_file("config.json").into_os_string()),
+            ),
+            ("AU_B_FILEX", Some("value".into())),
+            ("AU_C_FILE", Some("".into())),
+            ("OTHER_AU_FILE", Some("value".into())),
+        ],
+        || {
+            let report = EnvironmentSecretFile::with_prefix("AU").audit();
+
+            assert!(report
+                .prefix_without_suffix
+                .contains(&"AU_B_FILEX".to_string()));
+            assert!(report.empty == vec!["AU_C_FILE".to_string()]);
+            assert!(report
+                .suffix_without_prefix
+                .contains(&"OTHER_AU_FILE".to_string()));
+            assert!(!report
+                .prefix_without_suffix
+                .contains(&"AU_A_FILE".to_string()));
+        },
+    )
+}
+
+#[test]
+fn test_with_suffix() {
+    temp_env::with_vars(
+        vec![
+            ("WS_A_SECRET", Some(get_test_file("config.json"))),
+            ("WS_B_FILE", Some(get_test_file("config.json"))),
+        ],
+        || {
+            let map = EnvironmentSecretFile::with_prefix_and_suffix("WS", "SECRET")
+                .collect()
+                .unwrap();
+            assert!(map.contains_key("a"));
+            assert!(!map.contains_key("b"));
+
+            let keys = EnvironmentSecretFile::with_suffix("SECRET").keys();
+            assert!(keys.contains(&"ws_a".to_string()));
+        },
+    )
+}
+
+#[test]
+fn test_file_source_required() {
+    temp_env::with_vars(
+        vec![
+            ("FS_A_FILE", Some(get_test_file("config.json"))),
+            ("FS_B_FILE", Some(get_test_file("not-available.json"))),
+        ],
+        || {
+            let options = Options::new().file_source(true);
+            let source = EnvironmentSecretFile::with_prefix("FS").options(options.clone());
+            assert!(source.collect().is_err());
+
+            let source = EnvironmentSecretFile::with_prefix("FS")
+                .options(options)
+                .required(false);
+            let map = source.collect().unwrap();
+            assert!(map.contains_key("a"));
+            assert!(!map.contains_key("b"));
+        },
+    )
+}
+
+#[test]
+fn test_origin_mode() {
+    use config_secret::OriginMode;
+
+    temp_env::with_var("OM_A_FILE", Some("config.json"), || {
+        let error = |mode: OriginMode| {
+            let options = Options::new().base_dir(get_test_file("")).origin_mode(mode);
+            let map = EnvironmentSecretFile::with_prefix("OM")
+                .options(options)
+                .collect()
+                .unwrap();
+            map["a"].clone().into_int().unwrap_err().to_string()
+        };
+
+        let path = get_test_file("config.json").display().to_string();
+        assert!(error(OriginMode::default()).contains(&path));
+        assert!(!error(OriginMode::default()).contains("secret:"));
+        assert!(error(OriginMode::Full).contains("secret:a:config.json"));
+        assert!(!error(OriginMode::None).contains("config.json"));
+    })
+}
+
+#[test]
+fn test_ignore_vars() {
+    temp_env::with_vars(
+        vec![
+            ("IV_A_FILE", Some(get_test_file("config.json"))),
+            ("IV_LEGACY_FILE", Some(get_test_file("not-available.json"))),
+        ],
+        || {
+            let source = EnvironmentSecretFile::with_prefix("IV").ignore_vars(["iv_legacy_file"]);
+            let map = source.collect().unwrap();
+            assert!(map.contains_key("a"));
+            assert!(!map.contains_key("legacy"));
+        },
+    )
+}
+
+#[test]
+fn test_uniform_separator() {
+    temp_env::with_var(
+        "US__REDIS__NODES__FILE",
+        Some(get_test_file("config.json")),
+        || {
+            let source = EnvironmentSecretFile::with_prefix("US")
+                .prefix_separator("_")
+                .uniform_separator("__");
+            assert!(source.keys() == vec!["redis.nodes"]);
+        },
+    )
+}
+
+#[test]
+fn test_require_nonempty() {
+    temp_env::with_var("RN_A_FILE", Some(get_test_file("config.json")), || {
+        let source = EnvironmentSecretFile::with_prefix("RN").require_nonempty(true);
+        assert!(source.collect().is_ok());
+
+        let source = EnvironmentSecretFile::with_prefix("RN_MISSPELLED").require_nonempty(true);
+        assert!(source.collect().is_err());
+    })
+}
+
+#[test]
+fn test_content_prefix() {
+    temp_env::with_vars(
+        vec![
+            (
+                "CP_A_FILE",
+                Some(get_test_file("config.json").into_os_string()),
+            ),
+            ("CP_SERVER_FILE", Some(r#"inline:{"port": 5000}"#.into())),
+        ],
+        || {
+            let options = Options::new().format(config::FileFormat::Json);
+            let source = EnvironmentSecretFile::with_prefix("CP")
+                .content_prefix("inline:")
+                .options(options);
+            let config = Config::builder().add_source(source).build().unwrap();
+
+            assert!(config.get::<u16>("server.port").unwrap() == 5000);
+            assert!(config.get::<u16>("a.server.port").unwrap() == 5000);
+        },
+    )
+}
+
+#[test]
+fn test_content_prefix_after_bom() {
+    use config_secret::MapVarProvider;
+
+    let vars =
+        MapVarProvider::new().with_var("CPB_SERVER_FILE", "\u{feff}  inline:{\"port\": 5000}");
+    let source = EnvironmentSecretFile::with_prefix("CPB")
+        .content_prefix("inline:")
+        .options(Options::new().format(config::FileFormat::Json))
+        .var_provider(vars);
+    let config = Config::builder().add_source(source).build().unwrap();
+
+    assert!(config.get::<u16>("server.port").unwrap() == 5000);
+}
+
+#[test]
+fn test_into_config() {
+    temp_env::with_var("ICF_FILE", Some(get_test_file("config.json")), || {
+        let config = EnvironmentSecretFile::with_prefix("ICF")
+            .into_config()
+            .unwrap();
+        assert!(config.get::<u16>("server.port").unwrap() == 5000);
+
+        let settings = EnvironmentSecretFile::with_prefix("ICF")
+            .load::<Settings>()
+            .unwrap();
+        assert!(settings.redis.nodes.len() == 3);
+    })
+}
+
+#[test]
+fn test_prefix_without_separator() {
+    temp_env::with_vars(
+        vec![
+            ("MYAPPDBHOST_FILE", Some(get_test_file("config.json"))),
+            ("MYAPP_FILE", Some(get_test_file("config.json"))),
+        ],
+        || {
+            let source = EnvironmentSecretFile::with_prefix("MYAPP").prefix_separator("");
+            assert!(source.keys() == vec!["", "dbhost"]);
+
+            let map = source.collect().unwrap();
+            assert!(map.contains_key("dbhost"));
+            assert!(map.contains_key("server"));
+        },
+    )
+}
+
+#[test]
+fn test_marker_position_prefix() {
+    temp_env::with_vars(
+        vec![
+            ("MP_FILE_DB", Some(get_test_file("config.json"))),
+            ("MP_REDIS_FILE", Some(get_test_file("config.json"))),
+        ],
+        || {
+            let source = EnvironmentSecretFile::with_prefix("MP").marker_position(Position::Prefix);
+            assert!(source.keys() == vec!["db"]);
+
+            let map = source.collect().unwrap();
+            assert!(map.contains_key("db"));
+            assert!(!map.contains_key("redis"));
+
+            let source = source.keep_prefix(true);
+            assert!(source.keys() == vec!["mp_db"]);
+
+            let message = source
+                .keep_prefix(false)
+                .require_keys(["cache"])
+                .collect()
+                .unwrap_err()
+                .to_string();
+            assert!(message.contains("`MP_FILE_CACHE`"));
+        },
+    )
+}
+
+#[test]
+fn test_marker_position_prefix_without_prefix() {
+    temp_env::with_var("FILE_MPX_HOST", Some(get_test_file("config.json")), || {
+        let source = EnvironmentSecretFile::default().marker_position(Position::Prefix);
+        assert!(source.keys().contains(&"mpx_host".to_string()));
+    })
+}
+
+#[test]
+fn test_value_transform() {
+    use config::{ConfigError, Map, Value};
+    use config_secret::SecretError;
+
+    fn invalid() -> SecretError {
+        SecretError::Config(ConfigError::Message("invalid connection string".into()))
+    }
+
+    temp_env::with_var("VT_DSN_FILE", Some(get_test_file("dsn")), || {
+        let split = |value: Value| {
+            let dsn = value.into_string()?;
+            let (host, rest) = dsn.split_once(':').ok_or_else(invalid)?;
+            let (port, database) = rest.split_once('/').ok_or_else(invalid)?;
+
+            let mut table = Map::new();
+            table.insert("host".to_string(), Value::from(host));
+            table.insert("port".to_string(), Value::from(port));
+            table.insert("database".to_string(), Value::from(database));
+            Ok(Value::from(table))
+        };
+
+        let source = EnvironmentSecretFile::with_prefix("VT")
+            .options(Options::new().raw(true))
+            .value_transform("dsn", split);
+        let dsn = source.collect().unwrap().remove("dsn").unwrap();
+        let dsn = dsn.into_table().unwrap();
+        assert!(dsn["host"].clone().into_string().unwrap() == "db.example.com");
+        assert!(dsn["port"].clone().into_int().unwrap() == 5432);
+        assert!(dsn["database"].clone().into_string().unwrap() == "app");
+
+        let source = source.value_transform("dsn", |_| Err(invalid()));
+        let message = source.collect().unwrap_err().to_string();
+        assert!(message.contains("VT_DSN_FILE"));
+        assert!(message.contains("invalid connection string"));
+    })
+}
+
+#[test]
+fn test_prefix_case_insensitive() {
+    use config_secret::MapVarProvider;
+
+    let vars = MapVarProvider::new()
+        .with_var("Ci_Db_File", "not-available.json")
+        .with_var("ci_file", "not-available.json")
+        .with_var("OTHER_FILE", "not-available.json")
+        .with_var("CIDB_FILE_X", "not-available.json")
+        .with_var("PATH", "/usr/bin");
+    let source = EnvironmentSecretFile::with_prefix("CI").var_provider(vars);
+
+    assert!(source.keys() == vec!["", "db"]);
+    assert!(source.audit().suffix_without_prefix == vec!["OTHER_FILE"]);
+}
+
+#[test]
+fn test_enable_if_var() {
+    let source = || EnvironmentSecretFile::with_prefix("EIV").enable_if_var("EIV_ENABLED", "true");
+
+    temp_env::with_var("EIV_DB_FILE", Some(get_test_file("config.json")), || {
+        assert!(source().collect().unwrap().is_empty());
+
+        temp_env::with_var("EIV_ENABLED", Some("yes"), || {
+            assert!(source().collect().unwrap().is_empty());
+        });
+
+        temp_env::with_var("EIV_ENABLED", Some("true"), || {
+            assert!(source().collect().unwrap().contains_key("db"));
+        });
+    })
+}
+
+#[test]
+fn test_upcase_keys() {
+    temp_env::with_vars(
+        vec![
+            ("UK_DB_HOST_FILE", Some(get_test_file("config.json"))),
+            ("UK_REDIS__NODES_FILE", Some(get_test_file("config.json"))),
+        ],
+        || {
+            let source = EnvironmentSecretFile::with_prefix("UK")
+                .separator("__")
+                .prefix_separator("_")
+                .suffix_separator("_")
+                .upcase_keys(true);
+            assert!(source.keys() == vec!["DB_HOST", "REDIS.NODES"]);
+
+            let map = source.collect().unwrap();
+            assert!(map.contains_key("DB_HOST"));
+            assert!(map.contains_key("REDIS.NODES"));
+
+            let source = source.upcase_keys(false);
+            assert!(source.keys() == vec!["db_host", "redis.nodes"]);
+        },
+    )
+}
+
+#[test]
+fn test_docker() {
+    use config_secret::MapVarProvider;
+
+    let vars = MapVarProvider::new()
+        .with_var(
+            "DK_DB_PASSWORD_FILE",
+            get_test_file("password").to_str().unwrap(),
+        )
+        .with_var("DK_DB_PASSWORD", "inline");
+    let source = EnvironmentSecretFile::docker("DK")
+        .options(Options::new().raw(true))
+        .var_provider(vars);
+
+    let explained = source.explain();
+    assert!(explained.contains("prefix pattern: \"dk_\""));
+    assert!(explained.contains("suffix pattern: \"_file\", after the key"));
+    assert!(explained.contains("separator: \"_\""));
+
+    let config = Config::builder().add_source(source).build().unwrap();
+    assert!(config.get::<String>("db.password").unwrap() == "  password");
+}
+
+#[test]
+fn test_three_segment_scopes() {
+    use config_secret::MapVarProvider;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct Services {
+        auth: Auth,
+        billing: Billing,
+    }
+
+    #[derive(Deserialize)]
+    struct Auth {
+        token: String,
+        db: Credentials,
+    }
+
+    #[derive(Deserialize)]
+    struct Billing {
+        key: String,
+        db: Credentials,
+    }
+
+    #[derive(Deserialize)]
+    struct Credentials {
+        password: String,
+        username: String,
+    }
+
+    let password = get_test_file("tree.d/db/password");
+    let username = get_test_file("tree.d/db/username");
+    let token = get_test_file("tree.d/cache/token");
+    let vars = MapVarProvider::new()
+        .with_var("SVC_AUTH_TOKEN_FILE", token.to_str().unwrap())
+        .with_var("SVC_AUTH_DB_PASSWORD_FILE", password.to_str().unwrap())
+        .with_var("SVC_AUTH_DB_USERNAME_FILE", username.to_str().unwrap())
+        .with_var("SVC_BILLING_KEY_FILE", token.to_str().unwrap())
+        .with_var("SVC_BILLING_DB_PASSWORD_FILE", password.to_str().unwrap())
+        .with_var("SVC_BILLING_DB_USERNAME_FILE", username.to_str().unwrap());
+    let source = EnvironmentSecretFile::with_prefix("SVC")
+        .separator("_")
+        .options(Options::new().raw(true))
+        .var_provider(vars);
+
+    let map = source.collect().unwrap();
+    assert!(map.contains_key("auth.db.password"));
+    assert!(map.contains_key("billing.db.username"));
+
+    let config = Config::builder().add_source(source).build().unwrap();
+    let services = config.try_deserialize::<Services>().unwrap();
+    assert!(services.auth.token == "token");
+    assert!(services.auth.db.password == "secret");
+    assert!(services.auth.db.username == "admin");
+    assert!(services.billing.key == "token");
+    assert!(services.billing.db.password == "secret");
+    assert!(services.billing.db.username == "admin");
+}
+
+#[test]
+fn test_three_segment_multichar_separator() {
+    use config_secret::MapVarProvider;
+
+    let token = get_test_file("tree.d/cache/token");
+    let vars =
+        MapVarProvider::new().with_var("SVD__AUTH__DB__TOKEN__FILE", token.to_str().unwrap());
+    let source = EnvironmentSecretFile::with_prefix("SVD")
+        .separator("__")
+        .options(Options::new().raw(true))
+        .var_provider(vars);
+
+    let map = source.collect().unwrap();
+    assert!(map.keys().collect::<Vec<_>>() == ["auth.db.token"]);
+}
+
+#[test]
+fn test_mutable_setters() {
+    use config_secret::MapVarProvider;
+
+    let vars = MapVarProvider::new()
+        .with_var(
+            "MS__DB__FILE",
+            get_test_file("config.json").to_str().unwrap(),
+        )
+        .with_var("MS__CACHE__FILE", "/not-available.json");
+
+    for optional in [true, false] {
+        let mut source = EnvironmentSecretFile::default();
+        source.set_prefix("MS").set_separator("__");
+        if optional {
+            source.set_required(false);
+        }
+        let source = source.var_provider(vars.clone());
+
+        if optional {
+            let map = source.collect().unwrap();
+            assert!(map.contains_key("db"));
+            assert!(!map.contains_key("cache"));
+        } else {
+            assert!(source.collect().is_err());
+        }
+    }
+}
+
+#[test]
+fn test_inline_secrets() {
+    use config::{Map, Value};
+    use config_secret::MapVarProvider;
+
+    let mut server = Map::new();
+    server.insert("host".to_string(), Value::from("127.0.0.1"));
+    server.insert("timeout".to_string(), Value::from(30));
+    let inline = vec![
+        ("server".to_string(), Value::from(server)),
+        ("token".to_string(), Value::from("inline-token")),
+    ];
+
+    let source = EnvironmentSecretFile::with_prefix("IS")
+        .var_provider(MapVarProvider::new())
+        .with_inline_secrets(inline.clone());
+    let debug = format!("{:?}", source);
+    assert!(debug.contains("\"token\""));
+    assert!(!debug.contains("inline-token"));
+
+    let config = Config::builder().add_source(source).build().unwrap();
+    assert!(config.get::<String>("server.host").unwrap() == "127.0.0.1");
+    assert!(config.get::<String>("token").unwrap() == "inline-token");
+
+    let vars =
+        MapVarProvider::new().with_var("IS_FILE", get_test_file("config.json").to_str().unwrap());
+    let source = EnvironmentSecretFile::with_prefix("IS")
+        .var_provider(vars)
+        .with_inline_secrets(inline);
+
+    let config = Config::builder()
+        .add_source(source.clone())
+        .build()
+        .unwrap();
+    assert!(config.get::<String>("server.host").unwrap() == "0.0.0.0");
+    assert!(config.get::<u32>("server.timeout").unwrap() == 30);
+    assert!(config.get::<u16>("server.port").unwrap() == 5000);
+
+    let source = source.inline_secrets_override(true);
+    let config = Config::builder().add_source(source).build().unwrap();
+    assert!(config.get::<String>("server.host").unwrap() == "127.0.0.1");
+    assert!(config.get::<u16>("server.port").unwrap() == 5000);
+}
+
+#[test]
+fn test_keyed_secrets_override_root_file() {
+    use config_secret::MapVarProvider;
+
+    let vars = MapVarProvider::new()
+        .with_var("MX_FILE", get_test_file("config.json").to_str().unwrap())
+        .with_var(
+            "MX_SERVER_FILE",
+            get_test_file("port.json").to_str().unwrap(),
+        )
+        .with_var(
+            "MX_REDIS_EXTRA_FILE",
+            get_test_file("port.json").to_str().unwrap(),
+        );
+    let source = EnvironmentSecretFile::with_prefix("MX")
+        .separator("_")
+        .var_provider(vars);
+
+    let map = source.collect().unwrap();
+    assert!(map.len() == 2);
+    let redis = map.get("redis").unwrap().clone().into_table().unwrap();
+    assert!(redis.contains_key("nodes"));
+    assert!(redis.contains_key("extra"));
+
+    let config = Config::builder().add_source(source).build().unwrap();
+    assert!(config.get::<u16>("server.port").unwrap() == 7000);
+    assert!(config.get::<String>("server.host").unwrap() == "0.0.0.0");
+    assert!(config.get::<u16>("redis.extra.port").unwrap() == 7000);
+}
+
+#[test]
+fn test_provenance() {
+    use config_secret::MapVarProvider;
+
+    let config = get_test_file("config.json");
+    let port = get_test_file("port.json");
+    let vars = MapVarProvider::new()
+        .with_var("PV_FILE", config.to_str().unwrap())
+        .with_var("PV_SERVER_FILE", port.to_str().unwrap())
+        .with_var("PV_TOKEN_FILE", "inline:{\"value\": \"secret\"}");
+    let source = EnvironmentSecretFile::with_prefix("PV")
+        .content_prefix("inline:")
+        .options(Options::new().key_format("token", config::FileFormat::Json))
+        .var_provider(vars);
+
+    assert!(source.provenance().is_empty());
+    source.collect().unwrap();
+
+    let provenance = source.clone().provenance();
+    assert!(provenance.len() == 2);
+    assert!(provenance["server"] == port);
+    assert!(provenance["redis"] == config);
+    assert!(!provenance.contains_key("token"));
+}
+
+#[test]
+fn test_empty_file_error() {
+    use config_secret::{EmptyFilePolicy, MapVarProvider};
+
+    let vars =
+        MapVarProvider::new().with_var("EF_DB_FILE", get_test_file("empty.yaml").to_str().unwrap());
+    let source = EnvironmentSecretFile::with_prefix("EF").var_provider(vars);
+    assert!(source.collect().is_ok());
+
+    let err = source
+        .options(Options::new().empty_files(EmptyFilePolicy::Error))
+        .collect()
+        .unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("EF_DB_FILE"));
+    assert!(message.contains("empty.yaml"));
+    assert!(message.contains("is empty"));
+}
+
+#[test]
+fn test_flatten_arrays() {
+    use config_secret::MapVarProvider;
+
+    let vars =
+        MapVarProvider::new().with_var("FA_FILE", get_test_file("config.json").to_str().unwrap());
+    let source = EnvironmentSecretFile::with_prefix("FA")
+        .flatten(true)
+        .var_provider(vars);
+
+    let map = source.collect().unwrap();
+    assert!(map.contains_key("server.host"));
+    assert!(map.contains_key("server.port"));
+    assert!(map.contains_key("redis.nodes"));
+
+    let source = source.flatten_arrays(true);
+    let map = source.collect().unwrap();
+    assert!(map.len() == 5);
+    assert!(map.contains_key("redis.nodes[0]"));
+    assert!(map.contains_key("redis.nodes[2]"));
+
+    let config = Config::builder().add_source(source).build().unwrap();
+    let settings = config.try_deserialize::<Settings>().unwrap();
+    assert!(settings.redis.nodes.len() == 3);
+    assert!(settings.redis.nodes[1] == "redis://10.0.0.2:6379");
+}
+
+#[test]
+fn test_check() {
+    use config_secret::{MapVarProvider, SecretError};
+
+    let vars = MapVarProvider::new()
+        .with_var("CK_DB_FILE", get_test_file("config.json").to_str().unwrap());
+    assert!(EnvironmentSecretFile::with_prefix("CK")
+        .var_provider(vars)
+        .check()
+        .is_ok());
+
+    let vars = MapVarProvider::new()
+        .with_var("CK_DB_FILE", get_test_file("config.json").to_str().unwrap())
+        .with_var(
+            "CK_CACHE_FILE",
+            get_test_file("broken.json").to_str().unwrap(),
+        );
+    let err = EnvironmentSecretFile::with_prefix("CK")
+        .var_provider(vars)
+        .check()
+        .unwrap_err();
+    assert!(matches!(err, SecretError::Var { ref var, .. } if var == "CK_CACHE_FILE"));
+}
+
+#[test]
+fn test_suffix_required_without_prefix() {
+    use config_secret::{MapVarProvider, SuffixRule};
+
+    let config = get_test_file("config.json");
+    let port = get_test_file("port.json");
+    let vars = MapVarProvider::new()
+        .with_var("SR_DB", config.to_str().unwrap())
+        .with_var("CACHE_FILE", port.to_str().unwrap())
+        .with_var("HOME", "/home/user");
+    let source = EnvironmentSecretFile::with_prefix("SR").var_provider(vars);
+    assert!(source.collect().unwrap().is_empty());
+
+    let map = source
+        .suffix_required_when(SuffixRule::WithoutPrefix)
+        .collect()
+        .unwrap();
+    assert!(map.len() == 2);
+    assert!(map.contains_key("db"));
+    assert!(map.contains_key("cache"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_manifest_var() {
+    use config_secret::MapVarProvider;
+
+    let manifest = format!(
+        r#"{{"db.password": "{}", "api.key": "{}"}}"#,
+        get_test_file("tree.d/db/password").display(),
+        get_test_file("tree.d/cache/token").display()
+    );
+    let vars = MapVarProvider::new()
+        .with_var("MF_SECRETS", &manifest)
+        .with_var(
+            "MF_OTHER_FILE",
+            get_test_file("config.json").to_str().unwrap(),
+        );
+    let source = EnvironmentSecretFile::with_prefix("MF")
+        .manifest_var("MF_SECRETS")
+        .options(Options::new().raw(true))
+        .var_provider(vars);
+
+    let map = source.collect().unwrap();
+    assert!(map.len() == 2);
+
+    let config = Config::builder().add_source(source).build().unwrap();
+    assert!(config.get::<String>("db.password").unwrap() == "secret");
+    assert!(config.get::<String>("api.key").unwrap() == "token");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_manifest_var_diagnostics() {
+    use config_secret::MapVarProvider;
+
+    let vars = MapVarProvider::new()
+        .with_var(
+            "MK_SECRETS",
+            r#"{"db.password": "/run/secrets/dbpw", "api.key": "/run/secrets/api"}"#,
+        )
+        .with_var("MK_OTHER_FILE", "/run/secrets/other");
+    let source = EnvironmentSecretFile::with_prefix("MK")
+        .manifest_var("MK_SECRETS")
+        .var_provider(vars);
+
+    assert!(source.keys() == vec!["api.key", "db.password"]);
+    let explain = source.explain();
+    assert!(explain.contains("MK_SECRETS -> db.password (manifest)"));
+    assert!(!explain.contains("MK_OTHER_FILE"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_manifest_var_missing_file() {
+    use config_secret::MapVarProvider;
+
+    let manifest = format!(
+        r#"{{"db.password": "{}", "api.key": "/not-available"}}"#,
+        get_test_file("tree.d/db/password").display()
+    );
+    let vars = MapVarProvider::new().with_var("MM_SECRETS", &manifest);
+    let source = EnvironmentSecretFile::default()
+        .manifest_var("MM_SECRETS")
+        .options(Options::new().raw(true))
+        .var_provider(vars);
+
+    let err = source.collect().unwrap_err().to_string();
+    assert!(err.contains("MM_SECRETS"));
+    assert!(err.contains("/not-available"));
+
+    let map = source.required(false).collect().unwrap();
+    assert!(map.len() == 1);
+    assert!(map.contains_key("db.password"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_manifest_var_malformed() {
+    use config_secret::{MapVarProvider, SecretError};
+
+    for (manifest, kind) in [
+        (
+            r#"{"db.password": "/run/secrets/hunter2""#,
+            "truncated JSON",
+        ),
+        (r#"{"db.password": 12345678}"#, "expected a JSON object"),
+        (r#"{"db.password" "/run/secrets/hunter2"}"#, "invalid JSON"),
+    ] {
+        let vars = MapVarProvider::new().with_var("MB_SECRETS", manifest);
+        let source = EnvironmentSecretFile::default()
+            .manifest_var("MB_SECRETS")
+            .var_provider(vars);
+
+        let err = source.check().unwrap_err();
+        assert!(matches!(err, SecretError::Manifest { ref var, .. } if var == "MB_SECRETS"));
+
+        let message = err.to_string();
+        assert!(message.contains(kind), "{}", message);
+        assert!(message.contains("line 1 column"));
+        assert!(!message.contains("hunter2") && !message.contains("12345678"));
+        assert!(!message.contains("inline content"));
+    }
+}
+
+#[test]
+fn test_explain() {
+    use config_secret::MapVarProvider;
+
+    let vars = MapVarProvider::new()
+        .with_var("EX_DB_FILE", "/run/secrets/db-value")
+        .with_var("EX_FILE", "/run/secrets/root-value")
+        .with_var("EX_CACHE_FILE", "")
+        .with_var("EX_DB", "inline-value")
+        .with_var("OTHER_FILE", "other-value")
+        .with_var("EX_LEGACY_FILE", "legacy-value")
+        .with_var("PATH", "/usr/bin");
+    let source = EnvironmentSecretFile::with_prefix("EX")
+        .separator("__")
+        .prefix_separator("_")
+        .suffix_separator("_")
+        .ignore_vars(["EX_LEGACY_FILE"])
+        .var_provider(vars);
+
+    let explained = source.explain();
+    assert!(explained.contains("prefix pattern: \"ex_\""));
+    assert!(explained.contains("suffix pattern: \"_file\", after the key"));
+    assert!(explained.contains("root pattern: \"ex_file\""));
+    assert!(explained.contains("separator: \"__\", replaced with the key delimiter \".\""));
+    assert!(explained.contains("matched:\n  EX_DB_FILE -> db\n  EX_FILE -> (root)\n"));
+    assert!(explained.contains("matched but empty:\n  EX_CACHE_FILE\n"));
+    assert!(explained.contains("prefix without suffix:\n  EX_DB\n"));
+    assert!(explained.contains("suffix without prefix:\n  OTHER_FILE\n"));
+    assert!(explained.contains("rejected by filters:\n  EX_LEGACY_FILE\n"));
+    assert!(explained.contains("other variables: 1"));
+    assert!(!explained.contains("value"));
+}
+
+#[test]
+fn test_require_keys() {
+    temp_env::with_var("RK_FILE", Some(get_test_file("config.json")), || {
+        let source = EnvironmentSecretFile::with_prefix("RK")
+            .separator("__")
+            .prefix_separator("_")
+            .suffix_separator("_")
+            .require_keys(["server.port", "db.password"]);
+
+        let message = source.collect().unwrap_err().to_string();
+        assert!(message.contains("`db.password`, set `RK_DB__PASSWORD_FILE`"));
+        assert!(!message.contains("server.port"));
+    })
+}
+
+#[test]
+fn test_require_keys_lists_every_spelling() {
+    use config_secret::{MapVarProvider, SecretError, SuffixRule};
+
+    let source = EnvironmentSecretFile::with_prefix("RKS")
+        .separators(vec!["_", "-"])
+        .suffix_required_when(SuffixRule::WithoutPrefix)
+        .require_keys(["db.password"])
+        .var_provider(MapVarProvider::new());
+
+    let missing = match source.check().unwrap_err() {
+        SecretError::MissingKeys { missing } => missing,
+        err => panic!("unexpected error: {}", err),
+    };
+    assert!(missing.len() == 1);
+    assert!(missing[0].0 == "db.password");
+
+    let names = &missing[0].1;
+    assert!(names.len() > 1);
+    for name in [
+        "RKS_DB_PASSWORD_FILE",
+        "RKS_DB_PASSWORD",
+        "DB_PASSWORD_FILE",
+        "RKS-DB-PASSWORD-FILE",
+    ] {
+        assert!(
+            names.iter().any(|n| n == name),
+            "{} not in {:?}",
+            name,
+            names
+        );
+    }
+}
+
+#[test]
+fn test_keys() {
+    temp_env::with_vars(
+        vec![
+            ("KS_FILE", Some("not-available.json")),
+            ("KS_REDIS_NODES_FILE", Some("not-available.json")),
+            ("KS_SERVER_FILE", Some("not-available.json")),
+            ("KS_EMPTY_FILE", Some("")),
+        ],
+        || {
+            let source = EnvironmentSecretFile::with_prefix("KS").separator("_");
+            assert!(source.keys() == vec!["", "redis.nodes", "server"]);
+        },
+    )
+}
+
+#[test]
+fn test_required() {
+    temp_env::with_var(
+        "RQ_A_FILE",
+        Some(get_test_file("not-available.json")),
+        || {
+            let source = EnvironmentSecretFile::with_prefix("RQ");
+            assert!(source.collect().is_err());
+
+            let source = EnvironmentSecretFile::with_prefix("RQ").required(false);
+            assert!(!source.collect().unwrap().contains_key("a"));
+        },
+    )
+}
+
+#[test]
+fn test_path_list() {
+    let paths = std::env::join_paths(vec![
+        get_test_file("config.json"),
+        get_test_file("not-available.json"),
+        get_test_file("override.json"),
+    ])
+    .unwrap();
+
+    temp_env::with_var("PL_A_FILE", Some(paths), || {
+        let source = EnvironmentSecretFile::with_prefix("PL")
+            .separator("_")
+            .path_list(true);
+        assert!(source.collect().is_err());
+
+        let source = EnvironmentSecretFile::with_prefix("PL")
+            .separator("_")
+            .path_list(true)
+            .required(false);
+
+        let config = Config::builder().add_source(source).build().unwrap();
+        let settings = config.try_deserialize::<ScopedSettings>().unwrap();
+
+        assert!(settings.a.server.host == "0.0.0.0");
+        assert!(settings.a.server.port == 6000);
+        assert!(settings.a.redis.nodes.len() == 3);
+    })
+}
+
+#[test]
+fn test_last_stats() {
+    temp_env::with_vars(
+        vec![
+            ("ST_A_FILE", Some(get_test_file("config.json"))),
+            ("ST_B_FILE", Some(get_test_file("config.yaml"))),
+        ],
+        || {
+            let source = EnvironmentSecretFile::with_prefix("ST");
+            assert!(source.last_stats().files == 0);
+
+            source.collect().unwrap();
+
+            let size = |file| std::fs::metadata(get_test_file(file)).unwrap().len();
+            let stats = source.last_stats();
+            assert!(stats.files == 2);
+            assert!(stats.bytes == size("config.json") + size("config.yaml"));
+        },
+    )
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_last_stats_custom_reader() {
+    use config_secret::{CollectStats, MapReader, MapVarProvider, Options};
+
+    let vars = MapVarProvider::new().with_var("SC_TOKEN_FILE", "/secrets/token.json");
+    let files = MapReader::new().file("/secrets/token.json", "{\"a\": 1}\n");
+    let source = EnvironmentSecretFile::with_prefix("SC")
+        .var_provider(vars)
+        .options(Options::new().reader(files));
+
+    source.collect().unwrap();
+    assert!(source.last_stats() == CollectStats { files: 1, bytes: 9 });
+}
+
+#[test]
+fn test_seed_keys_fallback_pattern() {
+    temp_env::with_vars(
+        vec![
+            ("SK_SERVER_FILE", Some(get_test_file("override.json"))),
+            ("SK_EXTRA_FILE", Some(get_test_file("config.json"))),
+        ],
+        || {
+            // Base configuration provided by another source
+            let base = Config::builder()
+                .add_source(config::File::from(get_test_file("config.json")))
+                .build()
+                .unwrap();
+            let provided = base.collect().unwrap().into_keys();
+
+            // Secrets only fill in keys missing from the base configuration
+            let secrets = EnvironmentSecretFile::with_prefix("SK").seed_keys(provided);
+            let map = secrets.collect().unwrap();
+            assert!(!map.contains_key("server"));
+            assert!(map.contains_key("extra"));
+
+            let config = Config::builder()
+                .add_source(base)
+                .add_source(secrets)
+                .build()
+                .unwrap();
+            let settings = config.try_deserialize::<Settings>().unwrap();
+            assert!(settings.server.port == 5000);
+        },
+    )
+}
+
+#[test]
+fn test_optional_keys() {
+    temp_env::with_vars(
+        vec![
+            ("OK_DB_PASSWORD_FILE", Some(get_test_file("config.json"))),
+            (
+                "OK_TRACING_TOKEN_FILE",
+                Some(get_test_file("not-available.json")),
+            ),
+        ],
+        || {
+            let source = EnvironmentSecretFile::with_prefix("OK").separator("_");
+            assert!(source.collect().is_err());
+
+            let source = EnvironmentSecretFile::with_prefix("OK")
+                .separator("_")
+                .optional_keys(vec!["tracing.token"]);
+            let map = source.collect().unwrap();
+            assert!(map.contains_key("db.password"));
+            assert!(!map.contains_key("tracing.token"));
+        },
+    )
+}
+
+#[test]
+fn test_optional_keys_others_stay_required() {
+    temp_env::with_var(
+        "OR_DB_PASSWORD_FILE",
+        Some(get_test_file("not-available.json")),
+        || {
+            let source = EnvironmentSecretFile::with_prefix("OR")
+                .separator("_")
+                .optional_keys(vec!["tracing.token"]);
+            assert!(source.collect().is_err());
+        },
+    )
+}
+
+#[test]
+fn test_collect_all_or_nothing() {
+    let source = EnvironmentSecretFile::with_prefix("AON").cache(true);
+
+    temp_env::with_vars(
+        vec![
+            ("AON_A_FILE", Some(get_test_file("config.json"))),
+            ("AON_B_FILE", Some(get_test_file("not-available.json"))),
+        ],
+        || {
+            assert!(source.collect().is_err());
+            assert!(source.last_stats().files == 0);
+        },
+    );
+
+    temp_env::with_var("AON_C_FILE", Some(get_test_file("config.json")), || {
+        let map = source.collect().unwrap();
+        assert!(!map.contains_key("a"));
+        assert!(map.contains_key("c"));
+        assert!(source.last_stats().files == 1);
+    });
+}
@@ -0,0 +1,52 @@
+use config::{Config, File, FileFormat, Source};
+use config_secret::{EnvironmentSecretFile, SecretOptions};
+
+mod helpers;
+use crate::helpers::get_test_file;
+
+const BASE: &str = r#"
+{
+    "secrets": {
+        "prefix": "SO",
+        "separator": "__",
+        "prefix_separator": "_",
+        "suffix_separator": "_",
+        "optional_keys": ["cache"]
+    }
+}
+"#;
+
+fn options(json: &str) -> Result<SecretOptions, config::ConfigError> {
+    Config::builder()
+        .add_source(File::from_str(json, FileFormat::Json))
+        .build()?
+        .get("secrets")
+}
+
+#[test]
+fn test_from_options() {
+    temp_env::with_vars(
+        vec![
+            ("SO_DB__MAIN_FILE", Some(get_test_file("config.json"))),
+            ("SO_CACHE_FILE", Some(get_test_file("not-available.json"))),
+        ],
+        || {
+            let options = options(BASE).unwrap();
+            assert!(options.prefix.as_deref() == Some("SO"));
+            assert!(options.required.is_none());
+
+            let source = EnvironmentSecretFile::from_options(options);
+            assert!(source.keys() == vec!["cache", "db.main"]);
+
+            let map = source.collect().unwrap();
+            assert!(map.contains_key("db.main"));
+            assert!(!map.contains_key("cache"));
+        },
+    )
+}
+
+#[test]
+fn test_from_options_unknown_field() {
+    let err = options(r#"{ "secrets": { "prefx": "SO" } }"#).unwrap_err();
+    assert!(err.to_string().contains("prefx"));
+}
@@ -0,0 +1,118 @@
+#![cfg(all(feature = "tar", feature = "json", feature = "yaml"))]
+
+use config::{Config, Source};
+use config_secret::TarSecret;
+
+mod helpers;
+use crate::helpers::{get_test_file, Settings};
+
+#[test]
+fn test_members_are_inserted_by_path() {
+    let source = TarSecret::new(get_test_file("secrets.tar"));
+    let map = source.collect().unwrap();
+
+    assert!(map.contains_key("server"));
+    assert!(map.contains_key("redis"));
+    assert!(map.contains_key("nested.token"));
+    assert!(!map.contains_key("nested"));
+}
+
+#[test]
+fn test_serialize() {
+    let source = TarSecret::new(get_test_file("secrets.tar"));
+
+    let config = Config::builder().add_source(source).build().unwrap();
+
+    let settings = config.try_deserialize::<Settings>().unwrap();
+
+    assert!(settings.server.host == "0.0.0.0");
+    assert!(settings.server.port == 5000);
+    assert!(settings.redis.nodes.len() == 3);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_gzip_archive() {
+    let source = TarSecret::new(get_test_file("secrets.tar.gz"));
+    let map = source.collect().unwrap();
+
+    assert!(map.contains_key("server"));
+    assert!(map.contains_key("redis"));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_gzip_option() {
+    use std::{env, fs};
+
+    let path = env::temp_dir().join(format!("config-secret-bundle-{}", std::process::id()));
+    fs::copy(get_test_file("secrets.tar.gz"), &path).unwrap();
+
+    let map = TarSecret::new(&path).gzip(true).collect().unwrap();
+    assert!(map.contains_key("server"));
+    assert!(TarSecret::new(&path).collect().is_err());
+    fs::remove_file(&path).unwrap();
+
+    let source = TarSecret::new(get_test_file("secrets.tar.gz")).gzip(false);
+    assert!(source.collect().is_err());
+}
+
+#[test]
+fn test_member_options() {
+    use config_secret::{EmptyFilePolicy, Options};
+
+    let source = TarSecret::new(get_test_file("pipeline.tar"))
+        .key_delimiter("_")
+        .options(Options::new().raw(true));
+    let map = source.collect().unwrap();
+    assert!(map["db_password"].clone().into_string().unwrap() == "c2VjcmV0");
+    assert!(map["empty"].clone().into_string().unwrap().is_empty());
+
+    let err = TarSecret::new(get_test_file("pipeline.tar"))
+        .options(Options::new().raw(true).empty_files(EmptyFilePolicy::Error))
+        .collect()
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("failed to load member empty"));
+    assert!(err.contains("secret file empty is empty"));
+}
+
+#[cfg(feature = "binary")]
+#[test]
+fn test_member_base64() {
+    use config_secret::Options;
+
+    let source = TarSecret::new(get_test_file("pipeline.tar"))
+        .options(Options::new().raw(true).base64(true));
+    let map = source.collect().unwrap();
+    assert!(map["db.password"].clone().into_string().unwrap() == "secret");
+}
+
+#[test]
+fn test_error_names_member() {
+    let source = TarSecret::new(get_test_file("invalid.tar"));
+    let err = source.collect().unwrap_err();
+
+    assert!(err.to_string().contains("broken.json"));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_corrupt_gzip_archive() {
+    let err = TarSecret::new(get_test_file("corrupt.tar.gz"))
+        .collect()
+        .unwrap_err();
+
+    assert!(err.to_string().contains("failed to decompress"));
+}
+
+#[test]
+fn test_last_stats() {
+    let source = TarSecret::new(get_test_file("secrets.tar"));
+    assert!(source.last_stats().files == 0);
+
+    let map = source.collect().unwrap();
+    let stats = source.last_stats();
+    assert!(stats.files == map.len());
+    assert!(stats.bytes > 0);
+}
@@ -0,0 +1,24 @@
+#![cfg(feature = "test-util")]
+
+use config_secret::{collect_into_map, EnvironmentSecretFile, FileSecret};
+
+mod helpers;
+use crate::helpers::get_test_file;
+
+#[test]
+fn test_collect_into_map() {
+    let map = collect_into_map(&FileSecret::new("app", get_test_file("config.yaml")));
+
+    assert!(map["app.server.host"] == "0.0.0.0");
+    assert!(map["app.server.port"] == "5000");
+    assert!(map["app.redis.nodes[2]"] == "redis://10.0.0.3:6379");
+    assert!(map.len() == 5);
+}
+
+#[test]
+#[should_panic(expected = "failed to collect the secret source")]
+fn test_collect_into_map_error() {
+    temp_env::with_var("TU_A_FILE", Some("not-available.json"), || {
+        collect_into_map(&EnvironmentSecretFile::with_prefix("TU"));
+    })
+}
@@ -0,0 +1,27 @@
+#![cfg(feature = "tilde")]
+
+use config_secret::{load_value, Options};
+
+mod helpers;
+use crate::helpers::get_test_file;
+
+#[test]
+fn test_expand_tilde() {
+    let assets = get_test_file("");
+
+    temp_env::with_var("HOME", Some(&assets), || {
+        let path = std::path::Path::new("~/config.json");
+
+        assert!(load_value(path, &Options::default()).is_err());
+
+        let options = Options::new().expand_tilde(true);
+        assert!(load_value(path, &options).is_ok());
+    })
+}
+
+#[test]
+fn test_expand_tilde_only_leading() {
+    let options = Options::new().expand_tilde(true);
+    let path = get_test_file("~/config.json");
+    assert!(load_value(&path, &options).is_err());
+}
@@ -0,0 +1,99 @@
+use config::Source;
+use config_secret::{DotenvVarProvider, EnvironmentSecretFile, MapVarProvider, VarProvider};
+
+mod helpers;
+use crate::helpers::get_test_file;
+
+#[test]
+fn test_map_provider() {
+    let path = get_test_file("config.json");
+    let vars = MapVarProvider::new().with_var("MV_A_FILE", path.to_str().unwrap());
+
+    let source = EnvironmentSecretFile::with_prefix("MV").var_provider(vars);
+    assert!(source.collect().unwrap().contains_key("a"));
+}
+
+#[test]
+fn test_map_provider_ignores_process_env() {
+    temp_env::with_var("MP_A_FILE", Some(get_test_file("config.json")), || {
+        let source = EnvironmentSecretFile::with_prefix("MP").var_provider(MapVarProvider::new());
+        assert!(source.collect().unwrap().is_empty());
+    })
+}
+
+#[test]
+fn test_dotenv_provider() {
+    let vars = DotenvVarProvider::from_path(get_test_file("secrets.env")).unwrap();
+    assert!(VarProvider::var(&vars, "OTHER") == Some("value".to_string()));
+
+    let source = EnvironmentSecretFile::with_prefix("DE").var_provider(vars);
+    let map = source.collect().unwrap();
+    assert!(map.contains_key("a"));
+    assert!(map.contains_key("b"));
+}
+
+#[test]
+fn test_provider_debug_hides_values() {
+    let vars = MapVarProvider::new().with_var("MD_TOKEN", "map-secret");
+    let debug = format!("{:?}", vars);
+    assert!(debug.contains("\"MD_TOKEN\""));
+    assert!(!debug.contains("map-secret"));
+
+    let source = EnvironmentSecretFile::with_prefix("MD").var_provider(vars);
+    let debug = format!("{:?}", source);
+    assert!(debug.contains("\"MD_TOKEN\""));
+    assert!(!debug.contains("map-secret"));
+
+    let vars = DotenvVarProvider::from_path(get_test_file("secrets.env")).unwrap();
+    let debug = format!("{:?}", vars);
+    assert!(debug.contains("\"OTHER\""));
+    assert!(!debug.contains("value"));
+}
+
+#[test]
+fn test_dotenv_provider_nofile() {
+    assert!(DotenvVarProvider::from_path(get_test_file("not-available.env")).is_err());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_map_reader() {
+    use config_secret::{MapReader, Options};
+
+    let vars = MapVarProvider::new().with_var("MR_REDIS_FILE", "/secrets/redis.json");
+    let files = MapReader::new().file("/secrets/redis.json", r#"{ "password": "secret" }"#);
+
+    let source = EnvironmentSecretFile::with_prefix("MR")
+        .var_provider(vars)
+        .options(Options::new().reader(files));
+
+    let map = source.collect().unwrap();
+    let redis = map["redis"].clone().into_table().unwrap();
+    assert_eq!(redis["password"].clone().into_string().unwrap(), "secret");
+}
+
+#[test]
+fn test_map_reader_nofile() {
+    use config_secret::{MapReader, Options};
+
+    let vars = MapVarProvider::new().with_var("MN_REDIS_FILE", "/secrets/redis.json");
+    let source = EnvironmentSecretFile::with_prefix("MN")
+        .var_provider(vars)
+        .options(Options::new().reader(MapReader::new()));
+
+    assert!(source.collect().is_err());
+}
+
+#[test]
+fn test_map_reader_debug_hides_content() {
+    use config_secret::{MapReader, Options};
+
+    let files = MapReader::new().file("/secrets/token", "reader-secret");
+    let debug = format!("{:?}", files);
+    assert!(debug.contains("/secrets/token"));
+    assert!(!debug.contains("reader-secret"));
+
+    let debug = format!("{:?}", Options::new().reader(files));
+    assert!(debug.contains("/secrets/token"));
+    assert!(!debug.contains("reader-secret"));
+}
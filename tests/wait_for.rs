@@ -0,0 +1,128 @@
+use std::{
+    env, fs, thread,
+    time::{Duration, Instant},
+};
+
+use config::Source;
+use config_secret::{EnvironmentSecretFile, MapVarProvider, Options};
+
+mod helpers;
+use crate::helpers::get_test_file;
+
+#[test]
+fn test_wait_for_late_file() {
+    let path = env::temp_dir().join(format!("config-secret-wait-{}", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let vars = MapVarProvider::new().with_var("WF_TOKEN_FILE", path.to_str().unwrap());
+    let source = EnvironmentSecretFile::with_prefix("WF")
+        .var_provider(vars)
+        .options(Options::new().raw(true))
+        .wait_for(Duration::from_secs(10));
+
+    let writer = {
+        let path = path.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            fs::write(path, "late").unwrap();
+        })
+    };
+
+    let start = Instant::now();
+    let map = source.collect().unwrap();
+    writer.join().unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(map.get("token").unwrap().clone().into_string().unwrap() == "late");
+}
+
+#[test]
+fn test_wait_for_partially_mounted() {
+    let path = env::temp_dir().join(format!("config-secret-partial-{}", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let config = get_test_file("config.json");
+    let vars = MapVarProvider::new()
+        .with_var("WP_APP_FILE", config.to_str().unwrap())
+        .with_var("WP_TOKEN_FILE", path.to_str().unwrap());
+    let source = EnvironmentSecretFile::with_prefix("WP")
+        .var_provider(vars)
+        .options(Options::new().raw(true))
+        .wait_for(Duration::from_secs(10));
+
+    let writer = {
+        let path = path.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            fs::write(path, "late").unwrap();
+        })
+    };
+
+    let map = source.collect().unwrap();
+    writer.join().unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(map.contains_key("app"));
+    assert!(map.get("token").unwrap().clone().into_string().unwrap() == "late");
+}
+
+#[test]
+fn test_wait_for_timeout() {
+    let missing = get_test_file("not-available.json");
+    let vars = MapVarProvider::new().with_var("WT_DB_FILE", missing.to_str().unwrap());
+    let source = EnvironmentSecretFile::with_prefix("WT")
+        .var_provider(vars)
+        .wait_for(Duration::from_millis(300));
+
+    let start = Instant::now();
+    assert!(source.clone().collect().is_err());
+    assert!(start.elapsed() >= Duration::from_millis(300));
+
+    let map = source.required(false).collect().unwrap();
+    assert!(map.is_empty());
+}
+
+#[test]
+fn test_wait_for_existing_file() {
+    let config = get_test_file("config.json");
+    let vars = MapVarProvider::new().with_var("WE_APP_FILE", config.to_str().unwrap());
+    let source = EnvironmentSecretFile::with_prefix("WE")
+        .var_provider(vars)
+        .wait_for(Duration::from_secs(10));
+
+    let start = Instant::now();
+    assert!(source.collect().unwrap().contains_key("app"));
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_wait_for_manifest() {
+    let path = env::temp_dir().join(format!("config-secret-manifest-{}", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let manifest = format!(r#"{{"token": "{}"}}"#, path.display());
+    let vars = MapVarProvider::new().with_var("WM_SECRETS", &manifest);
+    let source = EnvironmentSecretFile::with_prefix("WM")
+        .manifest_var("WM_SECRETS")
+        .var_provider(vars)
+        .options(Options::new().raw(true))
+        .wait_for(Duration::from_secs(10));
+
+    let writer = {
+        let path = path.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            fs::write(path, "late").unwrap();
+        })
+    };
+
+    let start = Instant::now();
+    let map = source.collect().unwrap();
+    writer.join().unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(map.get("token").unwrap().clone().into_string().unwrap() == "late");
+}